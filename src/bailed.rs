@@ -0,0 +1,165 @@
+#[cfg(feature = "std")]
+use std::boxed::Box;
+
+use crate::{BailSite, IntoResult};
+
+/// An error that records where a bail happened, for propagating bails as errors.
+///
+/// This is returned by [`or_return_bailed!`](crate::or_return_bailed) and
+/// [`or_return_bailed_quiet!`](crate::or_return_bailed_quiet). With the `std` feature, it also
+/// holds the failure value as its [`source`](core::error::Error::source) if the failure value is an
+/// error.
+///
+/// This type implements [`IntoResult`] as a failure, so a caller can log it with the bail macros
+/// once it decides to.
+///
+/// # Example
+///
+/// ```rust
+/// use tiny_bail::{Bailed, prelude::ret::*};
+///
+/// fn parse(s: &str) -> Result<i32, Bailed> {
+///     // Return the bail site without logging it.
+///     let n = rq_bailed!(s.parse::<i32>());
+///     Ok(n * 2)
+/// }
+///
+/// fn run() {
+///     // Log the original bail site.
+///     let n = r!(parse("x"));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Bailed {
+    site: BailSite,
+    #[cfg(feature = "std")]
+    source: Option<Box<dyn core::error::Error + Send + Sync>>,
+}
+
+impl Bailed {
+    /// Create a new `Bailed` error from its bail site.
+    pub fn new(site: BailSite) -> Self {
+        Self {
+            site,
+            #[cfg(feature = "std")]
+            source: None,
+        }
+    }
+
+    /// Set the source error.
+    #[cfg(feature = "std")]
+    pub fn with_source(
+        mut self,
+        source: impl Into<Box<dyn core::error::Error + Send + Sync>>,
+    ) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Return the bail site.
+    pub fn site(&self) -> BailSite {
+        self.site
+    }
+}
+
+impl core::fmt::Display for Bailed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(msg) = self.site.msg {
+            write!(f, "{msg}: ")?;
+        }
+        write!(f, "Bailed at {}: `{}` failed", self.site, self.site.expr)
+    }
+}
+
+impl core::error::Error for Bailed {
+    #[cfg(feature = "std")]
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        self.source.as_deref().map(|e| e as _)
+    }
+}
+
+impl IntoResult<(), Bailed> for Bailed {
+    fn into_result(self) -> Result<(), Bailed> {
+        Err(self)
+    }
+}
+
+/// A wrapper around the failure value, taken by value.
+pub struct Own<T>(pub T);
+
+/// Select the failure value as the source if it's an error.
+pub trait ViaError {
+    /// Create a `Bailed` error from the bail site and failure value.
+    fn __bailed(self, site: BailSite) -> Bailed;
+}
+
+#[cfg(feature = "std")]
+impl<T: core::error::Error + Send + Sync + 'static> ViaError for Own<T> {
+    fn __bailed(self, site: BailSite) -> Bailed {
+        Bailed::new(site).with_source(self.0)
+    }
+}
+
+/// Discard the failure value otherwise.
+pub trait ViaOpaque {
+    /// Create a `Bailed` error from the bail site and failure value.
+    fn __bailed(self, site: BailSite) -> Bailed;
+}
+
+impl<T> ViaOpaque for &Own<T> {
+    fn __bailed(self, site: BailSite) -> Bailed {
+        Bailed::new(site)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bailed, Own};
+    use crate::{BailSite, IntoResult as _};
+
+    const SITE: BailSite = BailSite {
+        file: "src/example.rs",
+        line: 3,
+        column: 5,
+        expr: "x",
+        msg: None,
+    };
+
+    #[test]
+    // The explicit borrows are needed for autoref specialization.
+    #[allow(clippy::needless_borrow)]
+    fn specialization() {
+        #[allow(unused_imports)]
+        use super::{ViaError as _, ViaOpaque as _};
+        use core::error::Error as _;
+
+        // Failure values that implement `Error` should become the source, if possible.
+        let bailed = Own("x".parse::<i32>().unwrap_err()).__bailed(SITE);
+        assert_eq!(bailed.source().is_some(), cfg!(feature = "std"));
+
+        // Other failure values should be discarded.
+        let bailed = Own(None::<()>).__bailed(SITE);
+        assert!(bailed.source().is_none());
+        assert_eq!(bailed.site(), SITE);
+    }
+
+    #[test]
+    fn display() {
+        let bailed = Bailed::new(SITE);
+        assert_eq!(
+            format!("{bailed}"),
+            "Bailed at src/example.rs:3:5: `x` failed",
+        );
+        assert!(bailed.into_result().is_err());
+
+        // The message should be included as a prefix, if set.
+        let bailed = Bailed::new(BailSite {
+            msg: Some("missing x"),
+            ..SITE
+        });
+        assert_eq!(
+            format!("{bailed}"),
+            "missing x: Bailed at src/example.rs:3:5: `x` failed",
+        );
+    }
+}
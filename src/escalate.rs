@@ -0,0 +1,100 @@
+use crate::{
+    __atomic::{AtomicU32, Ordering},
+    Level,
+};
+
+/// The maximum number of escalation thresholds, enough to escalate from `trace` to `error`.
+const MAX_THRESHOLDS: usize = 4;
+
+/// The current escalation thresholds, padded with `u32::MAX`.
+static THRESHOLDS: [AtomicU32; MAX_THRESHOLDS] = [
+    AtomicU32::new(10_000),
+    AtomicU32::new(u32::MAX),
+    AtomicU32::new(u32::MAX),
+    AtomicU32::new(u32::MAX),
+];
+
+/// Set the hit thresholds at which the `escalate` macros escalate their log level.
+///
+/// Each bail site starts at the current log level. Once the site has bailed as many times as a
+/// threshold, its log level is escalated one step closer to [`Level::Error`]. Thresholds should be
+/// in ascending order, and only the first four are used.
+///
+/// Defaults to `[10_000]`, escalating each bail site by one level after 10,000 hits.
+///
+/// The thresholds are process-wide: each bail site counts its own hits, but every site is
+/// compared against the same thresholds.
+///
+/// # Example
+///
+/// ```rust
+/// // Escalate from `warn` to `error` after 100 hits, or from `debug` to `info` to `warn` after 100
+/// // and 1,000 hits.
+/// tiny_bail::set_escalation(&[100, 1_000]);
+/// ```
+pub fn set_escalation(thresholds: &[u32]) {
+    for (i, threshold) in THRESHOLDS.iter().enumerate() {
+        threshold.store(
+            thresholds.get(i).copied().unwrap_or(u32::MAX),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// Escalate a log level by one step per threshold reached.
+fn escalate(level: Level, hits: u32, thresholds: impl IntoIterator<Item = u32>) -> Level {
+    if level == Level::Off {
+        return level;
+    }
+
+    let steps = thresholds
+        .into_iter()
+        .filter(|&threshold| hits >= threshold)
+        .count();
+    Level::from_u8(
+        (level as u8)
+            .saturating_sub(steps as u8)
+            .max(Level::Error as u8),
+    )
+}
+
+/// Record a hit on a bail site and return its log level, escalated from the given log level.
+#[doc(hidden)]
+pub fn __escalate(hits: &AtomicU32, level: Level) -> Level {
+    // Quiet bails don't count as hits.
+    if level == Level::Off {
+        return level;
+    }
+
+    let hits = match hits.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_add(1)) {
+        Ok(n) => n + 1,
+        Err(n) => n,
+    };
+    let thresholds = THRESHOLDS
+        .iter()
+        .map(|threshold| threshold.load(Ordering::Relaxed));
+    escalate(level, hits, thresholds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escalate;
+    use crate::Level;
+
+    #[test]
+    fn escalate_level() {
+        let thresholds = [10, 100, u32::MAX, u32::MAX];
+
+        // The log level should escalate by one step per threshold reached.
+        assert_eq!(escalate(Level::Debug, 1, thresholds), Level::Debug);
+        assert_eq!(escalate(Level::Debug, 10, thresholds), Level::Info);
+        assert_eq!(escalate(Level::Debug, 99, thresholds), Level::Info);
+        assert_eq!(escalate(Level::Debug, 100, thresholds), Level::Warn);
+        assert_eq!(escalate(Level::Debug, u32::MAX, thresholds), Level::Error);
+
+        // The log level should not escalate past `error`, or at all from `off`.
+        assert_eq!(escalate(Level::Warn, 100, thresholds), Level::Error);
+        assert_eq!(escalate(Level::Error, 100, thresholds), Level::Error);
+        assert_eq!(escalate(Level::Off, 100, thresholds), Level::Off);
+    }
+}
@@ -0,0 +1,100 @@
+use core::fmt::{Debug, Display, Formatter, Result};
+
+use crate::{
+    __atomic::{AtomicBool, Ordering},
+    BailSite,
+};
+
+/// Whether [`or_exit!`](crate::or_exit) includes the code location in its message.
+static LOCATION: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+
+/// Set whether [`or_exit!`](crate::or_exit) includes the code location in its message.
+///
+/// Defaults to `true` in debug builds, and `false` in release builds.
+///
+/// # Example
+///
+/// ```rust
+/// // Keep the code location out of error messages shown to users.
+/// tiny_bail::set_exit_location(false);
+/// ```
+pub fn set_exit_location(enabled: bool) {
+    LOCATION.store(enabled, Ordering::Relaxed);
+}
+
+/// Return whether [`or_exit!`](crate::or_exit) includes the code location in its message.
+#[doc(hidden)]
+pub fn __exit_location() -> bool {
+    LOCATION.load(Ordering::Relaxed)
+}
+
+/// The message printed to stderr by [`or_exit!`](crate::or_exit) before exiting.
+pub struct ExitMessage<'a> {
+    /// The bail site.
+    pub site: &'a BailSite,
+    /// The failure value.
+    pub err: &'a dyn Debug,
+    /// Whether to include the code location.
+    pub location: bool,
+}
+
+impl Display for ExitMessage<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str("error: ")?;
+        if let Some(msg) = self.site.msg {
+            write!(f, "{msg}: ")?;
+        }
+        write!(f, "`{}` is `{:?}`", self.site.expr, self.err)?;
+        if self.location {
+            write!(f, " (at {})", self.site)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExitMessage;
+    use crate::BailSite;
+
+    #[test]
+    fn display() {
+        let site = BailSite {
+            file: "src/main.rs",
+            line: 3,
+            column: 5,
+            expr: "args.next()",
+            msg: None,
+        };
+
+        // The code location should only be included if requested.
+        let message = ExitMessage {
+            site: &site,
+            err: &None::<()>,
+            location: true,
+        };
+        assert_eq!(
+            message.to_string(),
+            "error: `args.next()` is `None` (at src/main.rs:3:5)",
+        );
+        let message = ExitMessage {
+            location: false,
+            ..message
+        };
+        assert_eq!(message.to_string(), "error: `args.next()` is `None`");
+
+        // The message should be included as a prefix, if set.
+        let site = BailSite {
+            msg: Some("missing argument"),
+            ..site
+        };
+        let message = ExitMessage {
+            site: &site,
+            ..message
+        };
+        assert_eq!(
+            message.to_string(),
+            "error: missing argument: `args.next()` is `None`",
+        );
+    }
+}
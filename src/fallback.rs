@@ -0,0 +1,686 @@
+//! The fallback log backend, used when no log backend feature is set.
+//!
+//! If a sink has been registered with [`set_sink`], bail messages are passed to it. Otherwise, with
+//! the `std` feature, bail messages are written to stderr, and without it they're discarded.
+//!
+//! With the `std` feature, install a different writer with `set_writer` or `set_fmt_writer`, and
+//! customize the message prefix with `set_timestamp` and `set_thread_name`.
+
+use core::{fmt::Debug, ptr};
+#[cfg(feature = "std")]
+use std::{
+    fmt, io,
+    sync::{Mutex, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "std")]
+use crate::__atomic::AtomicBool;
+use crate::{
+    __atomic::{AtomicPtr, Ordering},
+    BailSite, Level,
+};
+
+/// A function that receives the bail site, log level, failure value, and captured variables on
+/// bail.
+pub type Sink = fn(&BailSite, Level, &dyn Debug, &[Capture]);
+
+/// The name and value of a variable captured into the bail message.
+pub type Capture<'a> = (&'static str, &'a dyn Debug);
+
+/// The registered sink as a type-erased function pointer, or null if no sink is registered.
+static SINK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Pass bail messages to a sink instead of the default behavior.
+///
+/// # Example
+///
+/// ```rust
+/// use core::fmt::Debug;
+/// use tiny_bail::{BailSite, Level, fallback::Capture};
+///
+/// fn sink(site: &BailSite, level: Level, err: &dyn Debug, captures: &[Capture]) {
+///     // Forward `site`, `level`, `err`, and `captures` to a serial port, RTT channel, etc.
+/// }
+///
+/// tiny_bail::fallback::set_sink(sink);
+/// ```
+pub fn set_sink(sink: Sink) {
+    SINK.store(sink as *mut (), Ordering::Release);
+}
+
+/// Unregister the sink, restoring the default behavior.
+pub fn reset_sink() {
+    SINK.store(ptr::null_mut(), Ordering::Release);
+}
+
+/// The installed writer, or `None` to write to stderr.
+#[cfg(feature = "std")]
+static WRITER: Mutex<Option<Writer>> = Mutex::new(None);
+
+/// Whether to prefix each message with a timestamp.
+#[cfg(feature = "std")]
+static TIMESTAMP: AtomicBool = AtomicBool::new(false);
+
+/// Whether to prefix each message with the current thread's name.
+#[cfg(feature = "std")]
+static THREAD_NAME: AtomicBool = AtomicBool::new(false);
+
+/// A user-installed writer.
+#[cfg(feature = "std")]
+enum Writer {
+    Io(Box<dyn io::Write + Send>),
+    Fmt(Box<dyn fmt::Write + Send>),
+}
+
+/// Write bail messages to an [`io::Write`] sink instead of stderr.
+///
+/// # Example
+///
+/// ```no_run
+/// // Write bail messages to a file, prefixed with a timestamp and thread name.
+/// let file = std::fs::File::create("bail.log").unwrap();
+/// tiny_bail::fallback::set_writer(file);
+/// tiny_bail::fallback::set_timestamp(true);
+/// tiny_bail::fallback::set_thread_name(true);
+/// ```
+#[cfg(feature = "std")]
+pub fn set_writer(writer: impl io::Write + Send + 'static) {
+    *WRITER.lock().unwrap_or_else(PoisonError::into_inner) = Some(Writer::Io(Box::new(writer)));
+}
+
+/// Write bail messages to a [`fmt::Write`] sink instead of stderr.
+#[cfg(feature = "std")]
+pub fn set_fmt_writer(writer: impl fmt::Write + Send + 'static) {
+    *WRITER.lock().unwrap_or_else(PoisonError::into_inner) = Some(Writer::Fmt(Box::new(writer)));
+}
+
+/// Write bail messages to stderr, dropping any installed writer.
+#[cfg(feature = "std")]
+pub fn reset_writer() {
+    *WRITER.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Set whether to prefix bail messages with a timestamp (disabled by default).
+///
+/// The timestamp is the number of seconds since the Unix epoch, with millisecond precision.
+#[cfg(feature = "std")]
+pub fn set_timestamp(enabled: bool) {
+    TIMESTAMP.store(enabled, Ordering::Relaxed);
+}
+
+/// Set whether to prefix bail messages with the current thread's name (disabled by default).
+#[cfg(feature = "std")]
+pub fn set_thread_name(enabled: bool) {
+    THREAD_NAME.store(enabled, Ordering::Relaxed);
+}
+
+/// A bail message along with its configured prefix.
+#[cfg(feature = "std")]
+struct Line<'a> {
+    site: &'a BailSite,
+    err: &'a dyn Debug,
+    captures: &'a [Capture<'a>],
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Line<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if TIMESTAMP.load(Ordering::Relaxed) {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            write!(f, "{}.{:03} ", now.as_secs(), now.subsec_millis())?;
+        }
+        if THREAD_NAME.load(Ordering::Relaxed) {
+            let thread = std::thread::current();
+            write!(f, "[{}] ", thread.name().unwrap_or("<unnamed>"))?;
+        }
+        if let Some(msg) = self.site.msg {
+            write!(f, "{msg}: ")?;
+        }
+        write!(
+            f,
+            "Bailed at {}: `{}` is `{:?}`",
+            self.site, self.site.expr, self.err,
+        )?;
+        for (name, value) in self.captures {
+            write!(f, ", {name} = `{value:?}`")?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a bail message to the installed writer.
+#[cfg(feature = "std")]
+fn write(site: &BailSite, err: &dyn Debug, captures: &[Capture]) {
+    // Format before taking the lock, so a `Debug` impl that bails doesn't deadlock.
+    let line = Line {
+        site,
+        err,
+        captures,
+    }
+    .to_string();
+    let mut writer = WRITER.lock().unwrap_or_else(PoisonError::into_inner);
+    // Errors are discarded, since there's nowhere left to report them.
+    let _ = match writer.as_mut() {
+        None => {
+            use io::Write as _;
+            writeln!(io::stderr().lock(), "{line}")
+        }
+        Some(Writer::Io(writer)) => writeln!(writer, "{line}"),
+        Some(Writer::Fmt(writer)) => writeln!(writer, "{line}").map_err(io::Error::other),
+    };
+}
+
+/// Pass a bail message to the registered sink, or fall back to the default behavior.
+#[doc(hidden)]
+pub fn __log(site: &BailSite, level: Level, err: &dyn Debug, captures: &[Capture]) {
+    let sink = SINK.load(Ordering::Acquire);
+    if !sink.is_null() {
+        // SAFETY: Non-null values of `SINK` are always valid `Sink` function pointers.
+        let sink = unsafe { core::mem::transmute::<*mut (), Sink>(sink) };
+        sink(site, level, err, captures);
+    } else {
+        #[cfg(feature = "std")]
+        write(site, err, captures);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Capture;
+    use crate::{BailSite, Level};
+    use core::{
+        fmt::Debug,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+    use std::sync::Mutex;
+
+    fn bail() {
+        crate::or_return!(None::<()>);
+    }
+
+    #[test]
+    fn sink() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        fn sink(site: &BailSite, _level: Level, err: &dyn Debug, _captures: &[Capture]) {
+            assert_eq!(site.file, "src/fallback.rs");
+            assert_eq!(site.expr, "None::<()>");
+            assert_eq!(format!("{err:?}"), "None");
+            CALLS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let _lock = crate::tests::LOCK.lock().unwrap();
+        // Bail messages may be stripped at compile time.
+        if !crate::level().__is_static_enabled() {
+            return;
+        }
+
+        super::set_sink(sink);
+        bail();
+        bail();
+        super::reset_sink();
+        bail();
+
+        // The sink should have been called for each bail until it was unregistered.
+        assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn level() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        fn sink(_site: &BailSite, _level: Level, _err: &dyn Debug, _captures: &[Capture]) {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let _lock = crate::tests::LOCK.lock().unwrap();
+        let level = crate::level();
+        super::set_sink(sink);
+        crate::set_level(Level::Off);
+        bail();
+        crate::set_level(Level::Error);
+        bail();
+        crate::set_level(Level::Trace);
+        bail();
+        super::reset_sink();
+        crate::set_level(level);
+
+        // The sink should only have been called while the log level was enabled.
+        let expected = [Level::Error, Level::Trace]
+            .into_iter()
+            .filter(|level| level.__is_static_enabled())
+            .count();
+        assert_eq!(CALLS.load(Ordering::Relaxed), expected);
+    }
+
+    /// Run `f` with a sink that records each bail message as its message, expression, failure
+    /// value, and captured variables.
+    ///
+    /// Returns `None` if bail messages are stripped at compile time.
+    fn record(f: impl FnOnce()) -> Option<Vec<String>> {
+        let lines = record_levels(f)?;
+        Some(lines.into_iter().map(|(_, line)| line).collect())
+    }
+
+    /// Like [`record`], but also records the log level of each bail message.
+    fn record_levels(f: impl FnOnce()) -> Option<Vec<(Level, String)>> {
+        static LINES: Mutex<Vec<(Level, String)>> = Mutex::new(Vec::new());
+        fn sink(site: &BailSite, level: Level, err: &dyn Debug, captures: &[Capture]) {
+            let mut line = site.msg.map(|msg| format!("{msg}: ")).unwrap_or_default();
+            line += &format!("`{}` is `{err:?}`", site.expr);
+            for (name, value) in captures {
+                line += &format!(", {name} = {value:?}");
+            }
+            LINES.lock().unwrap().push((level, line));
+        }
+
+        let _lock = crate::tests::LOCK.lock().unwrap();
+        // Bail messages may be stripped at compile time.
+        if !crate::level().__is_static_enabled() {
+            return None;
+        }
+
+        LINES.lock().unwrap().clear();
+        super::set_sink(sink);
+        f();
+        super::reset_sink();
+        Some(core::mem::take(&mut *LINES.lock().unwrap()))
+    }
+
+    #[test]
+    fn quiet_if() {
+        fn bail(x: Result<i32, i32>) -> i32 {
+            crate::or_return!(5, x, quiet_if = |&e| e < 0)
+        }
+
+        let mut results = vec![];
+        let Some(lines) = record(|| {
+            results.push(bail(Err(-1)));
+            results.push(bail(Err(1)));
+        }) else {
+            return;
+        };
+
+        // Only the failure for which the predicate returned false should have been logged, but
+        // both failures should have returned early.
+        assert_eq!(lines, ["`x` is `1`"]);
+        assert_eq!(results, [5, 5]);
+    }
+
+    #[test]
+    fn cleanup() {
+        fn bail(x: Option<()>, cleanups: &mut u32) {
+            let key = 3;
+            crate::or_return!(x; key, else { *cleanups += 1; });
+        }
+
+        fn bail_quiet(x: Option<()>, cleanups: &mut u32) {
+            crate::or_return_quiet!(x; else { *cleanups += 1; });
+        }
+
+        let mut cleanups = 0;
+        let Some(lines) = record(|| {
+            bail(Some(()), &mut cleanups);
+            bail(None, &mut cleanups);
+            bail_quiet(None, &mut cleanups);
+        }) else {
+            return;
+        };
+
+        // The cleanup block should have run for each failure, whether or not it was logged.
+        assert_eq!(lines, ["`x` is `None`, key = 3"]);
+        assert_eq!(cleanups, 2);
+    }
+
+    #[test]
+    fn log_once_policy() {
+        #[derive(Debug)]
+        enum FetchError {
+            Quiet,
+            Loud,
+        }
+
+        impl crate::BailPolicy for FetchError {
+            fn bail_level(&self) -> Level {
+                match self {
+                    Self::Quiet => Level::Off,
+                    Self::Loud => crate::level(),
+                }
+            }
+        }
+
+        fn bail(x: Result<(), FetchError>) {
+            crate::or_return_log_once!(x);
+        }
+
+        let Some(lines) = record(|| {
+            bail(Err(FetchError::Quiet));
+            bail(Err(FetchError::Loud));
+            bail(Err(FetchError::Loud));
+        }) else {
+            return;
+        };
+
+        // Quiet failures shouldn't have used up the single log.
+        assert_eq!(lines, ["`x` is `Loud`"]);
+    }
+
+    #[test]
+    fn escalate() {
+        #[derive(Debug)]
+        enum FetchError {
+            Quiet,
+            Loud,
+        }
+
+        impl crate::BailPolicy for FetchError {
+            fn bail_level(&self) -> Level {
+                match self {
+                    Self::Quiet => Level::Off,
+                    Self::Loud => Level::Warn,
+                }
+            }
+        }
+
+        fn bail(x: Result<(), FetchError>) {
+            crate::or_return_escalate!(x);
+        }
+
+        let Some(lines) = record_levels(|| {
+            crate::set_escalation(&[2]);
+            bail(Err(FetchError::Quiet));
+            bail(Err(FetchError::Loud));
+            bail(Err(FetchError::Quiet));
+            bail(Err(FetchError::Loud));
+            bail(Err(FetchError::Loud));
+            crate::set_escalation(&[10_000]);
+        }) else {
+            return;
+        };
+
+        // Quiet failures shouldn't have counted towards the threshold.
+        let levels = lines
+            .into_iter()
+            .map(|(level, _)| level)
+            .collect::<Vec<_>>();
+        let expected = [Level::Warn, Level::Error, Level::Error]
+            .into_iter()
+            .filter(|level| level.__is_static_enabled())
+            .collect::<Vec<_>>();
+        assert_eq!(levels, expected);
+    }
+
+    #[test]
+    fn captures() {
+        fn bail(x: Option<()>) {
+            let key = 3;
+            let entity = "player";
+            crate::or_return!(x; key, entity);
+        }
+
+        let Some(lines) = record(|| {
+            bail(Some(()));
+            bail(None);
+        }) else {
+            return;
+        };
+
+        // The captured variables should have been logged on failure.
+        assert_eq!(lines, ["`x` is `None`, key = 3, entity = \"player\""]);
+    }
+
+    #[test]
+    fn comparison() {
+        fn bail(hp: i32) {
+            crate::or_return!(hp > 0);
+        }
+
+        #[derive(PartialEq)]
+        struct NotDebug;
+
+        fn bail_not_debug(x: NotDebug) {
+            crate::or_return!(x != NotDebug);
+        }
+
+        fn bail_long(hp: i32) {
+            crate::or_return!(hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp > 0);
+        }
+
+        fn bail_too_long(hp: i32) {
+            crate::or_return!(hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp > 0);
+        }
+
+        let Some(lines) = record(|| {
+            bail(3);
+            bail(-3);
+            bail_not_debug(NotDebug);
+            bail_long(0);
+            bail_too_long(0);
+        }) else {
+            return;
+        };
+
+        // Both operands should have been logged on failure, with a placeholder if needed. Past the
+        // first 24 token trees, the operands should have been left out.
+        assert_eq!(
+            lines,
+            [
+                "`hp > 0` is `false`, left = -3, right = 0",
+                "`x != NotDebug` is `false`, left = <unformattable>, right = <unformattable>",
+                "`hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp > 0` is `false`, \
+                 left = 0, right = 0",
+                "`hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp + hp > 0` is `false`",
+            ],
+        );
+    }
+
+    #[test]
+    fn pattern() {
+        fn bail(x: Result<i32, i32>) {
+            crate::or_return!(Ok(_) = x => ());
+        }
+
+        let Some(lines) = record(|| {
+            bail(Ok(3));
+            bail(Err(3));
+        }) else {
+            return;
+        };
+
+        // The value that didn't match should have been logged.
+        assert_eq!(lines, ["`x` is `Err(3)`"]);
+    }
+
+    #[test]
+    fn all() {
+        fn bail(x: Option<()>, y: Option<()>) {
+            let key = 3;
+            crate::or_return_all!(x, y; key);
+        }
+
+        let Some(lines) = record(|| {
+            bail(Some(()), Some(()));
+            bail(Some(()), None);
+        }) else {
+            return;
+        };
+
+        // The index of the failed expression should have been logged.
+        assert_eq!(lines, ["`y` is `None`, index = 1, key = 3"]);
+    }
+
+    #[test]
+    fn chain() {
+        fn bail(x: &[u32]) {
+            crate::or_return_chain!(x.first()?.checked_sub(1)?);
+        }
+
+        let Some(lines) = record(|| {
+            bail(&[1]);
+            bail(&[0]);
+        }) else {
+            return;
+        };
+
+        // The step that failed should have been logged.
+        assert_eq!(lines, ["`x.first()?.checked_sub(1)` is `None`"]);
+    }
+
+    #[test]
+    fn attempts() {
+        fn bail(x: Option<()>) -> u32 {
+            let key = 3;
+            let mut calls = 0;
+            crate::or_retry!(
+                3,
+                calls,
+                {
+                    calls += 1;
+                    x
+                },
+                backoff = Duration::from_millis(1);
+                key
+            );
+            calls
+        }
+
+        let mut results = vec![];
+        let Some(lines) = record(|| {
+            results.push(bail(Some(())));
+            results.push(bail(None));
+        }) else {
+            return;
+        };
+
+        // Each failed attempt should have been logged, before giving up after the last attempt.
+        assert_eq!(results, [1, 3]);
+        assert_eq!(
+            lines,
+            [
+                "`{ calls += 1; x }` is `None`, attempt = 1, key = 3",
+                "`{ calls += 1; x }` is `None`, attempt = 2, key = 3",
+                "`{ calls += 1; x }` is `None`, attempt = 3, key = 3",
+            ],
+        );
+    }
+
+    #[test]
+    fn msg_and_level() {
+        fn bail(x: Option<()>, level: Level) {
+            let key = 3;
+            crate::bail!(x, level = level, msg = "missing key"; key);
+        }
+
+        let Some(lines) = record(|| {
+            bail(None, Level::Error);
+            bail(None, Level::Off);
+        }) else {
+            return;
+        };
+
+        // The message should have been logged, but only at the enabled log level.
+        assert_eq!(lines, ["missing key: `x` is `None`, key = 3"]);
+    }
+
+    #[test]
+    fn default_options() {
+        crate::define_bail_macros! {
+            level: crate::Level::Error,
+            target: "tiny_bail::tests",
+            msg: "default",
+            aliases: (test_r, test_c, test_b),
+        }
+
+        fn bail(x: Option<()>) {
+            test_r!(x);
+        }
+
+        fn bail_with_msg(x: Option<()>) {
+            test_r!(x, msg = "override");
+        }
+
+        fn bail_quietly(x: Option<()>) {
+            test_r!(x, level = off);
+        }
+
+        let Some(lines) = record(|| {
+            bail(Some(()));
+            bail(None);
+            bail_with_msg(None);
+            bail_quietly(None);
+        }) else {
+            return;
+        };
+
+        // The default message should have been logged, then the overriding message.
+        assert_eq!(lines, ["default: `x` is `None`", "override: `x` is `None`"],);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn fmt_writer() {
+        use std::{
+            fmt,
+            sync::{Arc, Mutex},
+        };
+
+        #[derive(Clone, Default)]
+        struct SharedString(Arc<Mutex<String>>);
+
+        impl fmt::Write for SharedString {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0.lock().unwrap().write_str(s)
+            }
+        }
+
+        let _lock = crate::tests::LOCK.lock().unwrap();
+        // Bail messages may be stripped at compile time.
+        if !crate::level().__is_static_enabled() {
+            return;
+        }
+
+        let output = SharedString::default();
+        super::set_fmt_writer(output.clone());
+        super::set_thread_name(true);
+        bail();
+        let key = 3;
+        (|| crate::or_return!(None::<()>; key))();
+        (|| crate::or_return!(None::<()>, msg = "missing"))();
+        super::reset_writer();
+        super::set_thread_name(false);
+
+        // The message should have been written to the installed writer.
+        let output = output.0.lock().unwrap();
+        assert!(output.contains("[fallback::tests::fmt_writer] Bailed at src/fallback.rs:"));
+        assert!(output.contains("`None::<()>` is `None`"));
+        assert!(output.contains("`None::<()>` is `None`, key = `3`"));
+        assert!(
+            output.contains("[fallback::tests::fmt_writer] missing: Bailed at src/fallback.rs:")
+        );
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn reentrant_bail() {
+        struct Reentrant;
+
+        impl Debug for Reentrant {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                // Bailing while the outer bail message is being formatted shouldn't deadlock.
+                bail();
+                f.write_str("Reentrant")
+            }
+        }
+
+        let _lock = crate::tests::LOCK.lock().unwrap();
+        // Bail messages may be stripped at compile time.
+        if !crate::level().__is_static_enabled() {
+            return;
+        }
+
+        super::set_fmt_writer(String::new());
+        (|| crate::or_return!(Err::<(), _>(Reentrant)))();
+        super::reset_writer();
+    }
+}
@@ -0,0 +1,154 @@
+use crate::__atomic::{AtomicU8, Ordering};
+
+/// A log level for bail messages.
+///
+/// Levels are ordered by verbosity, from [`Level::Off`] to [`Level::Trace`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(u8)]
+pub enum Level {
+    /// Don't log.
+    Off,
+    /// The `error` log level.
+    Error,
+    /// The `warn` log level.
+    Warn,
+    /// The `info` log level.
+    Info,
+    /// The `debug` log level.
+    Debug,
+    /// The `trace` log level.
+    Trace,
+}
+
+/// The maximum log level, as set by the `max_level_*` and `release_max_level_*` features.
+///
+/// Bail messages above this level are stripped at compile time, leaving only the control flow.
+pub const STATIC_MAX_LEVEL: Level = if cfg!(debug_assertions) {
+    max_level()
+} else {
+    release_max_level()
+};
+
+/// The maximum log level, as set by the `max_level_*` features.
+const fn max_level() -> Level {
+    if cfg!(feature = "max_level_off") {
+        Level::Off
+    } else if cfg!(feature = "max_level_error") {
+        Level::Error
+    } else if cfg!(feature = "max_level_warn") {
+        Level::Warn
+    } else if cfg!(feature = "max_level_info") {
+        Level::Info
+    } else if cfg!(feature = "max_level_debug") {
+        Level::Debug
+    } else {
+        Level::Trace
+    }
+}
+
+/// The maximum log level in release builds, as set by the `release_max_level_*` features.
+const fn release_max_level() -> Level {
+    if cfg!(feature = "release_max_level_off") {
+        Level::Off
+    } else if cfg!(feature = "release_max_level_error") {
+        Level::Error
+    } else if cfg!(feature = "release_max_level_warn") {
+        Level::Warn
+    } else if cfg!(feature = "release_max_level_info") {
+        Level::Info
+    } else if cfg!(feature = "release_max_level_debug") {
+        Level::Debug
+    } else if cfg!(feature = "release_max_level_trace") {
+        Level::Trace
+    } else {
+        max_level()
+    }
+}
+
+/// The default log level for bail messages, as set by the log level features (`warn` by default).
+const DEFAULT_LEVEL: Level = if cfg!(feature = "trace") {
+    Level::Trace
+} else if cfg!(feature = "debug") {
+    Level::Debug
+} else if cfg!(feature = "info") {
+    Level::Info
+} else if cfg!(feature = "error") {
+    Level::Error
+} else {
+    Level::Warn
+};
+
+/// The current log level for bail messages.
+static LEVEL: AtomicU8 = AtomicU8::new(DEFAULT_LEVEL as u8);
+
+/// Set the log level for bail messages.
+///
+/// Bail messages will be logged at the new level, unless it's above [`STATIC_MAX_LEVEL`].
+/// Set [`Level::Off`] to silence bail messages.
+///
+/// # Example
+///
+/// ```rust
+/// use tiny_bail::Level;
+///
+/// // Escalate bail messages to errors.
+/// tiny_bail::set_level(Level::Error);
+/// assert_eq!(tiny_bail::level(), Level::Error);
+/// ```
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Return the current log level for bail messages.
+///
+/// Defaults to the level set by the log level features, or [`Level::Warn`] if none is set.
+pub fn level() -> Level {
+    Level::from_u8(LEVEL.load(Ordering::Relaxed))
+}
+
+/// Return the given log level, unless bail messages have been silenced with [`Level::Off`].
+#[doc(hidden)]
+pub fn __fixed_level(level: Level) -> Level {
+    match self::level() {
+        Level::Off => Level::Off,
+        _ => level,
+    }
+}
+
+impl Level {
+    /// Convert from the `u8` representation, treating invalid values as [`Level::Off`].
+    pub(crate) const fn from_u8(level: u8) -> Self {
+        match level {
+            1 => Level::Error,
+            2 => Level::Warn,
+            3 => Level::Info,
+            4 => Level::Debug,
+            5 => Level::Trace,
+            _ => Level::Off,
+        }
+    }
+
+    /// Convert to the equivalent [`log::Level`], or `None` if bail messages at this level are
+    /// disabled or stripped at compile time.
+    #[cfg(feature = "log")]
+    #[doc(hidden)]
+    pub const fn __log_level(self) -> Option<log::Level> {
+        if !self.__is_static_enabled() {
+            return None;
+        }
+        match self {
+            Level::Off => None,
+            Level::Error => Some(log::Level::Error),
+            Level::Warn => Some(log::Level::Warn),
+            Level::Info => Some(log::Level::Info),
+            Level::Debug => Some(log::Level::Debug),
+            Level::Trace => Some(log::Level::Trace),
+        }
+    }
+
+    /// Return whether bail messages at this level should be logged, or stripped at compile time.
+    #[doc(hidden)]
+    pub const fn __is_static_enabled(self) -> bool {
+        !matches!(self, Level::Off) && self as u8 <= STATIC_MAX_LEVEL as u8
+    }
+}
@@ -7,30 +7,105 @@
 //! The middle path avoids unwanted panics without the ergonomic challenges of propagating errors with `?`.
 //!
 //! This crate provides the following macro variants to determine the preferred behavior on failure:
+//! - [`bail!`]
 //! - [`or_return!`]
 //! - [`or_return_quiet!`]
 //! - [`or_return_log_once!`]
+//! - [`or_return_escalate!`]
+//! - [`or_return_all!`]
+//! - [`or_return_chain!`]
+//! - [`or_return_err!`]
+//! - [`or_return_bailed!`]
+//! - [`or_return_bailed_quiet!`]
+//! - [`or_retry!`]
+//! - [`or_exit!`]
 //! - [`or_continue!`]
 //! - [`or_continue_quiet!`]
 //! - [`or_continue_log_once!`]
+//! - [`or_continue_escalate!`]
+//! - [`or_continue_all!`]
+//! - [`or_continue_chain!`]
 //! - [`or_break!`]
 //! - [`or_break_quiet!`]
 //! - [`or_break_log_once!`]
+//! - [`or_break_escalate!`]
+//! - [`or_break_all!`]
+//! - [`or_break_chain!`]
+//! - [`or_default!`]
+//! - [`or_default_quiet!`]
+//! - [`or_default_log_once!`]
 //!
-//! Along with their tiny aliases:
-//! [`r!`](prelude::r),
-//! [`rq!`](prelude::rq),
-//! [`ro!`](prelude::ro),
-//! [`c!`](prelude::c),
-//! [`cq!`](prelude::cq),
-//! [`co!`](prelude::co),
-//! [`b!`](prelude::b),
-//! [`bq!`](prelude::bq), and
-//! [`bo!`](prelude::bo).
+//! Along with their tiny aliases, grouped into modules:
+//! - [`prelude::ret`], with `r!`, `rq!`, `ro!`, `re!`, `r_all!`, `r_chain!`, `r_err!`, `r_bailed!`,
+//!   `rq_bailed!`, and `rr!`.
+//! - [`prelude::cont`], with `c!`, `cq!`, `co!`, `ce!`, `c_all!`, and `c_chain!`.
+//! - [`prelude::brk`], with `b!`, `bq!`, `bo!`, `be!`, `b_all!`, and `b_chain!`.
+//! - [`prelude::exit`], with `ex!`.
+//! - [`prelude::or`], with `or!`, `orq!`, and `oro!`.
+//!
+//! The [`prelude`] glob import includes the most common tiny aliases: `r!`, `rq!`, `ro!`, `c!`,
+//! `cq!`, `co!`, `b!`, `bq!`, and `bo!`. Glob import a module like `tiny_bail::prelude::ret::*` for
+//! the rest of its group. [`bail!`], [`aliases!`], and [`define_bail_macros!`] aren't included in
+//! either glob import, so use them by path, as in `tiny_bail::bail!`.
+//!
+//! To avoid collisions with other single-letter macros, import [`explicit`] instead of [`prelude`],
+//! and pick your own aliases with [`aliases!`], as in `tiny_bail::aliases!(ret = r, cont = c)`.
 //!
 //! The macros support [`Result`], [`Option`], and [`bool`] types out of the box.
 //! Implement [`IntoResult`] to extend this to other types.
 //!
+//! Failure values can choose their own log level, or bail quietly, by implementing [`BailPolicy`].
+//! Individual call sites can also bail quietly with a `quiet_if = predicate` argument.
+//!
+//! Variables can be included in the bail message by listing them after a `;`, as in
+//! `r!(map.get(&key); key)`. With `tracing`, they're also recorded as structured fields.
+//!
+//! Comparisons like `r!(hp > 0)` include both operands in the bail message, as `left` and `right`.
+//! Operands that don't implement `Debug` are shown as a placeholder. The operator must be within the
+//! first 24 token trees of the expression for the operands to be included.
+//!
+//! Other enum variants can be unwrapped with a pattern, either let-else style with
+//! `r!(let Shape::Circle { r } = shape);` to bind `r` for the rest of the block, or with
+//! `r!(Shape::Circle { r } = shape => r)` to evaluate to a value.
+//!
+//! Several expressions can be unwrapped at once with `r_all!(a, b, c)`, which evaluates to a tuple
+//! and includes the `index` of the first failed expression in the bail message.
+//!
+//! Method chains like `r_chain!(map.get(&id)?.first()?.name())` unwrap each step that ends in `?`,
+//! and log the chain up to the first failed step. Steps after the last `?` are applied as-is.
+//!
+//! Breaks can carry a value out of a labeled block or `loop`, as in `b!('a => fallback, expr)`.
+//!
+//! Returns can compute their value from the failure with a closure, as in
+//! `r!(|e| Response::error(e), expr)`.
+//!
+//! In functions that return a `Result`, `r_err!(expr)` logs the failure and returns it as an error,
+//! like `?` with a bail message.
+//!
+//! Libraries can use `rq_bailed!(expr)` to return a [`Bailed`] error that records the bail site,
+//! leaving it to the caller to decide whether to log it.
+//!
+//! To fall back to a value without leaving the function, import [`prelude::or`] and use
+//! `or!(fallback, expr)`, or `or!(expr)` to fall back to the default value.
+//!
+//! Transient failures can be retried with `rr!(3, expr)`, or with `attempts = 3` in any bail macro,
+//! optionally with `backoff = duration` to sleep between attempts, doubling the duration after each
+//! failed attempt. Each failed attempt is logged.
+//!
+//! CLI tools can use `ex!(code, expr)` to print the failure to stderr and exit the process.
+//! The code location is included in debug builds, which can be changed with
+//! [`set_exit_location`].
+//!
+//! A cleanup block can be run on failure before bailing, as in
+//! `r!(expr; else { self.state = Idle; })`.
+//!
+//! All of these behaviors can also be configured with keyword options on the [`bail!`] macro, as in
+//! `bail!(expr, on_fail = continue 'outer, log = once, level = error, msg = "...")`.
+//!
+//! Each crate can also define its own bail macros with preset defaults, as in
+//! `tiny_bail::define_bail_macros! { level: error, target: "game::net", aliases: (r, c, b) }`.
+//! A default `msg` prefixes every bail message, and `strict: true` makes them panic on failure.
+//!
 //! # Example
 //!
 //! ```rust
@@ -46,7 +121,7 @@
 //!     if let Some(x) = arr.last_mut() {
 //!         *x += 1;
 //!     } else {
-//!         println!("Bailed at src/example.rs:34:18: `arr.last_mut()`");
+//!         eprintln!("Bailed at src/example.rs:34:18: `arr.last_mut()` is `None`");
 //!         return;
 //!     }
 //! }
@@ -63,57 +138,265 @@
 //! You can set features to customize the logging behavior on bail:
 //!
 //! ```shell
-//! # Log with `println!` instead of `tracing::warn!`.
+//! # Log to stderr instead of `tracing::warn!`.
+//! cargo add tiny_bail --no-default-features --features std
+//! # Discard bail messages or pass them to a registered sink, for `no_std` targets.
 //! cargo add tiny_bail --no-default-features
 //! # Log with `log::info!` instead of `tracing::warn!`.
 //! cargo add tiny_bail --no-default-features --features log,info
+//! # Log with `defmt::warn!` instead of `tracing::warn!`, for embedded targets.
+//! cargo add tiny_bail --no-default-features --features defmt,warn
+//! ```
+//!
+//! With `defmt`, your crate must also depend on `defmt` directly. Failure values that implement
+//! `defmt::Format` are logged as usual, while other failure values are logged with a placeholder.
+//! This is decided at the macro call site, so a generic failure value will always be logged with a
+//! placeholder.
+//!
+//! The log level can also be changed at runtime with [`set_level`], for example to
+//! silence bail messages with `Level::Off`.
+//!
+//! Like in `log`, you can set a maximum log level to strip bail messages above it at compile time,
+//! leaving only the control flow. With no log backend feature, bail messages are at the `warn`
+//! level unless a log level feature is set.
+//!
+//! ```shell
+//! # Strip bail messages above the `info` level in release builds.
+//! cargo add tiny_bail --features release_max_level_info
 //! ```
 //!
-//! This crate has zero dependencies other than the logging backend you choose (`log`, `tracing`, or nothing).
+//! On targets without atomic read-modify-write operations (e.g. `thumbv6m-none-eabi`), or without
+//! native atomics at all (e.g. `msp430-none-elf`), enable the `critical-section` feature and provide
+//! a [`critical-section`](https://docs.rs/critical-section) implementation:
+//!
+//! ```shell
+//! cargo add tiny_bail --no-default-features --features critical-section
+//! ```
+//!
+//! Alternatively, enable the `portable-atomic` feature and configure
+//! [`portable-atomic`](https://docs.rs/portable-atomic) yourself.
+//!
+//! This crate has no dependencies other than the logging backend you choose (`log`, `tracing`, `defmt`, or nothing),
+//! plus `portable-atomic` if the `portable-atomic` or `critical-section` feature is enabled.
+//!
+//! Without a log backend feature, bail messages are handled by the `fallback` backend, which
+//! writes them to stderr with the `std` feature or discards them without it. In both cases, the
+//! backend can be configured at runtime, for example to pass bail messages to a custom sink.
+//!
+//! Builds with `--no-default-features` used to print bail messages to stdout. They're now discarded
+//! unless the `std` feature is enabled, as in `features = ["std"]`, which writes them to stderr.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+mod bailed;
+mod escalate;
+mod exit;
+mod level;
+mod policy;
+
+pub use bailed::Bailed;
+#[doc(hidden)]
+pub use escalate::__escalate;
+pub use escalate::set_escalation;
+pub use exit::set_exit_location;
+#[doc(hidden)]
+pub use level::__fixed_level;
+pub use level::{Level, STATIC_MAX_LEVEL, level, set_level};
+pub use policy::BailPolicy;
+
+/// Helpers to select the log level of the failure value from its [`BailPolicy`], if possible.
+///
+/// Uses autoref specialization to select [`BailPolicy::bail_level`] if the failure value implements
+/// [`BailPolicy`], or the current log level otherwise.
+#[doc(hidden)]
+pub mod __policy {
+    pub use crate::policy::{ViaDefault, ViaPolicy, Wrap};
+}
+
+/// Helpers to create a [`Bailed`] error from the failure value.
+///
+/// Uses autoref specialization to keep the failure value as the source if it implements
+/// [`Error`](core::error::Error), or discard it otherwise.
+#[doc(hidden)]
+pub mod __bailed {
+    pub use crate::bailed::{Own, ViaError, ViaOpaque};
+}
+
+/// Helpers to print the failure before exiting the process.
+#[doc(hidden)]
+pub mod __exit {
+    pub use crate::exit::{__exit_location, ExitMessage};
+}
+
+#[cfg(not(any(feature = "log", feature = "tracing", feature = "defmt")))]
+pub mod fallback;
 
 /// Re-exported macros and tiny aliases.
 ///
-/// To omit tiny aliases, glob import [explicit] instead.
+/// The glob import includes the most common tiny aliases: `r!`, `rq!`, `ro!`, `c!`, `cq!`, `co!`,
+/// `b!`, `bq!`, and `bo!`. The full set of tiny aliases is grouped into modules, which can be glob
+/// imported on their own:
+/// [`ret`](prelude::ret), [`cont`](prelude::cont), [`brk`](prelude::brk),
+/// [`exit`](prelude::exit), and [`or`](prelude::or).
+///
+/// To omit tiny aliases, glob import [explicit] instead. To choose your own aliases, use
+/// [`aliases!`](crate::aliases).
 ///
 /// # Usage
 ///
 /// ```rust
 /// use tiny_bail::prelude::*;
+/// // Import the remaining return aliases, like `re!` and `rr!`.
+/// use tiny_bail::prelude::ret::*;
 /// ```
 pub mod prelude {
     pub use super::explicit::*;
+    pub use brk::{b, bo, bq};
+    pub use cont::{c, co, cq};
+    pub use ret::{r, ro, rq};
+
+    /// Tiny aliases for [`or_return!`](crate::or_return), its variants, and
+    /// [`or_retry!`](crate::or_retry).
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use tiny_bail::prelude::ret::*;
+    /// ```
+    pub mod ret {
+        /// Tiny alias for [`or_return!`](crate::or_return).
+        pub use crate::or_return as r;
+
+        /// Tiny alias for [`or_return_quiet!`](crate::or_return_quiet).
+        pub use crate::or_return_quiet as rq;
+
+        /// Tiny alias for [`or_return_log_once!`](crate::or_return_log_once).
+        pub use crate::or_return_log_once as ro;
+
+        /// Tiny alias for [`or_return_escalate!`](crate::or_return_escalate).
+        pub use crate::or_return_escalate as re;
+
+        /// Tiny alias for [`or_return_all!`](crate::or_return_all).
+        pub use crate::or_return_all as r_all;
+
+        /// Tiny alias for [`or_return_chain!`](crate::or_return_chain).
+        pub use crate::or_return_chain as r_chain;
+
+        /// Tiny alias for [`or_return_err!`](crate::or_return_err).
+        pub use crate::or_return_err as r_err;
+
+        /// Tiny alias for [`or_return_bailed!`](crate::or_return_bailed).
+        pub use crate::or_return_bailed as r_bailed;
+
+        /// Tiny alias for [`or_return_bailed_quiet!`](crate::or_return_bailed_quiet).
+        pub use crate::or_return_bailed_quiet as rq_bailed;
+
+        /// Tiny alias for [`or_retry!`](crate::or_retry).
+        pub use crate::or_retry as rr;
+    }
+
+    /// Tiny aliases for [`or_continue!`](crate::or_continue) and its variants.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use tiny_bail::prelude::cont::*;
+    /// ```
+    pub mod cont {
+        /// Tiny alias for [`or_continue!`](crate::or_continue).
+        pub use crate::or_continue as c;
+
+        /// Tiny alias for [`or_continue_quiet!`](crate::or_continue_quiet).
+        pub use crate::or_continue_quiet as cq;
+
+        /// Tiny alias for [`or_continue_log_once!`](crate::or_continue_log_once).
+        pub use crate::or_continue_log_once as co;
+
+        /// Tiny alias for [`or_continue_escalate!`](crate::or_continue_escalate).
+        pub use crate::or_continue_escalate as ce;
 
-    /// Tiny alias for [`or_return!`].
-    pub use or_return as r;
+        /// Tiny alias for [`or_continue_all!`](crate::or_continue_all).
+        pub use crate::or_continue_all as c_all;
 
-    /// Tiny alias for [`or_return_quiet!`].
-    pub use or_return_quiet as rq;
+        /// Tiny alias for [`or_continue_chain!`](crate::or_continue_chain).
+        pub use crate::or_continue_chain as c_chain;
+    }
+
+    /// Tiny aliases for [`or_break!`](crate::or_break) and its variants.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use tiny_bail::prelude::brk::*;
+    /// ```
+    pub mod brk {
+        /// Tiny alias for [`or_break!`](crate::or_break).
+        pub use crate::or_break as b;
+
+        /// Tiny alias for [`or_break_quiet!`](crate::or_break_quiet).
+        pub use crate::or_break_quiet as bq;
+
+        /// Tiny alias for [`or_break_log_once!`](crate::or_break_log_once).
+        pub use crate::or_break_log_once as bo;
 
-    /// Tiny alias for [`or_return_log_once!`].
-    pub use or_return_log_once as ro;
+        /// Tiny alias for [`or_break_escalate!`](crate::or_break_escalate).
+        pub use crate::or_break_escalate as be;
 
-    /// Tiny alias for [`or_continue!`].
-    pub use or_continue as c;
+        /// Tiny alias for [`or_break_all!`](crate::or_break_all).
+        pub use crate::or_break_all as b_all;
 
-    /// Tiny alias for [`or_continue_quiet!`].
-    pub use or_continue_quiet as cq;
+        /// Tiny alias for [`or_break_chain!`](crate::or_break_chain).
+        pub use crate::or_break_chain as b_chain;
+    }
+
+    /// Tiny alias for [`or_exit!`](crate::or_exit).
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use tiny_bail::prelude::exit::*;
+    /// ```
+    pub mod exit {
+        /// Tiny alias for [`or_exit!`](crate::or_exit).
+        pub use crate::or_exit as ex;
+    }
 
-    /// Tiny alias for [`or_continue_log_once!`].
-    pub use or_continue_log_once as co;
+    /// Tiny aliases for [`or_default!`](crate::or_default) and its variants.
+    ///
+    /// These aren't included in the glob prelude, since `or` is a common name.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use tiny_bail::prelude::or::*;
+    /// ```
+    pub mod or {
+        /// Tiny alias for [`or_default!`](crate::or_default).
+        pub use crate::or_default as or;
 
-    /// Tiny alias for [`or_break!`].
-    pub use or_break as b;
+        /// Tiny alias for [`or_default_quiet!`](crate::or_default_quiet).
+        pub use crate::or_default_quiet as orq;
 
-    /// Tiny alias for [`or_break_quiet!`].
-    pub use or_break_quiet as bq;
+        /// Tiny alias for [`or_default_log_once!`](crate::or_default_log_once).
+        pub use crate::or_default_log_once as oro;
+    }
+}
 
-    /// Tiny alias for [`or_break_log_once!`].
-    pub use or_break_log_once as bo;
+/// All macros and tiny aliases, to resolve the sources of [`aliases!`].
+#[doc(hidden)]
+pub mod __aliases {
+    pub use crate::explicit::*;
+    pub use crate::prelude::{brk::*, cont::*, exit::*, or::*, ret::*};
 }
 
 /// Re-exported macros.
 ///
-/// To include tiny aliases, glob import [prelude] instead.
+/// To include tiny aliases, glob import [prelude] instead, or choose your own aliases with
+/// [`aliases!`](crate::aliases).
+///
+/// [`bail!`](crate::bail), [`aliases!`](crate::aliases), and
+/// [`define_bail_macros!`](crate::define_bail_macros) aren't re-exported, so that a glob import
+/// doesn't claim their names. Use them by path instead, as in `tiny_bail::bail!`.
 ///
 /// # Usage
 ///
@@ -122,14 +405,21 @@ pub mod prelude {
 /// ```
 pub mod explicit {
     pub use super::{
-        or_break, or_break_log_once, or_break_quiet, or_continue, or_continue_log_once,
-        or_continue_quiet, or_return, or_return_log_once, or_return_quiet,
+        or_break, or_break_all, or_break_chain, or_break_escalate, or_break_log_once,
+        or_break_quiet, or_continue, or_continue_all, or_continue_chain, or_continue_escalate,
+        or_continue_log_once, or_continue_quiet, or_default, or_default_log_once, or_default_quiet,
+        or_exit, or_retry, or_return, or_return_all, or_return_bailed, or_return_bailed_quiet,
+        or_return_chain, or_return_err, or_return_escalate, or_return_log_once, or_return_quiet,
     };
 }
 
 // Require a sane feature combination.
-#[cfg(all(feature = "log", feature = "tracing"))]
-compile_error!("multiple log backend features are set (log, tracing)");
+#[cfg(any(
+    all(feature = "log", feature = "tracing"),
+    all(feature = "log", feature = "defmt"),
+    all(feature = "tracing", feature = "defmt"),
+))]
+compile_error!("multiple log backend features are set (log, tracing, defmt)");
 #[cfg(any(
     all(feature = "trace", feature = "debug"),
     all(feature = "trace", feature = "info"),
@@ -144,7 +434,7 @@ compile_error!("multiple log backend features are set (log, tracing)");
 ))]
 compile_error!("multiple log level features are set (trace, debug, info, warn, error)");
 #[cfg(all(
-    any(feature = "log", feature = "tracing"),
+    any(feature = "log", feature = "tracing", feature = "defmt"),
     not(any(
         feature = "trace",
         feature = "debug",
@@ -154,78 +444,465 @@ compile_error!("multiple log level features are set (trace, debug, info, warn, e
     )),
 ))]
 compile_error!(
-    "a log backend feature is set (log, tracing), but no log level feature is set (trace, debug, info, warn, error)",
-);
-#[cfg(all(
-    not(any(feature = "log", feature = "tracing")),
-    any(
-        feature = "trace",
-        feature = "debug",
-        feature = "info",
-        feature = "warn",
-        feature = "error",
-    ),
-))]
-compile_error!(
-    "a log level feature is set (trace, debug, info, warn, error), but no log backend feature is set (log, tracing)",
+    "a log backend feature is set (log, tracing, defmt), but no log level feature is set (trace, debug, info, warn, error)",
 );
 
 // Set the log backend.
 #[doc(hidden)]
 pub mod __log_backend {
     #[cfg(feature = "log")]
-    pub use log::{debug, error, info, trace, warn};
+    pub use log::log;
 
     #[cfg(feature = "tracing")]
     pub use tracing::{debug, error, info, trace, warn};
 
-    #[cfg(not(any(feature = "log", feature = "tracing")))]
-    pub use std::println;
-}
-
-/// Set the log level.
-macro_rules! set_log_level {
-    ($level:ident) => {
-        /// Log the code location, expression, and error on bail.
-        #[doc(hidden)]
-        #[macro_export]
-        macro_rules! ___log_bail {
-            ($expr:expr, $err:expr) => {
-                $crate::__log_backend::$level!(
-                    "Bailed at {}:{}:{}: `{}` is `{:?}`",
-                    file!(),
-                    line!(),
-                    column!(),
-                    stringify!($expr),
-                    $err,
+    #[cfg(feature = "defmt")]
+    pub use defmt::{debug, error, info, trace, warn};
+}
+
+/// Log the message, code location, expression, error, and captured variables on bail, at the given
+/// log level.
+///
+/// The `log` backend accepts a runtime level, so this expands to a single `log::log!` call.
+#[cfg(feature = "log")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_bail_at {
+    (
+        $target:tt,
+        $msg:tt,
+        $level:expr,
+        $expr:expr,
+        $err:expr
+        $(, $name:ident = $value:expr)*
+        $(,)?
+    ) => {
+        if const { $crate::STATIC_MAX_LEVEL.__is_static_enabled() } {
+            if let ::core::option::Option::Some(__level) = $crate::Level::__log_level($level) {
+                $crate::__log_bail_with!($target, $msg, __level, $expr, $err $(, $name = $value)*)
+            }
+        }
+    };
+}
+
+/// Log the message, code location, expression, error, and captured variables on bail, at the given
+/// log level.
+///
+/// The `tracing` and `defmt` backends require a static level, so each enabled level gets its own
+/// arm.
+#[cfg(any(feature = "tracing", feature = "defmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_bail_at {
+    (
+        $target:tt,
+        $msg:tt,
+        $level:expr,
+        $expr:expr,
+        $err:expr
+        $(, $name:ident = $value:expr)*
+        $(,)?
+    ) => {
+        $crate::__check_target!($target);
+        match $level {
+            $crate::Level::Error if const { $crate::Level::Error.__is_static_enabled() } => {
+                $crate::__log_bail_with!($target, $msg, error, $expr, $err $(, $name = $value)*)
+            }
+            $crate::Level::Warn if const { $crate::Level::Warn.__is_static_enabled() } => {
+                $crate::__log_bail_with!($target, $msg, warn, $expr, $err $(, $name = $value)*)
+            }
+            $crate::Level::Info if const { $crate::Level::Info.__is_static_enabled() } => {
+                $crate::__log_bail_with!($target, $msg, info, $expr, $err $(, $name = $value)*)
+            }
+            $crate::Level::Debug if const { $crate::Level::Debug.__is_static_enabled() } => {
+                $crate::__log_bail_with!($target, $msg, debug, $expr, $err $(, $name = $value)*)
+            }
+            $crate::Level::Trace if const { $crate::Level::Trace.__is_static_enabled() } => {
+                $crate::__log_bail_with!($target, $msg, trace, $expr, $err $(, $name = $value)*)
+            }
+            _ => {}
+        }
+    };
+}
+
+/// Check that the log target is supported by the log backend.
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_target {
+    ($target:tt) => {};
+}
+
+/// Check that the log target is supported by the log backend.
+///
+/// `defmt` has no log targets, so setting one is an error rather than silently ignored.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_target {
+    ([]) => {};
+    ([$($target:tt)+]) => {
+        ::core::compile_error!("the `target` bail option isn't supported with the `defmt` backend")
+    };
+}
+
+/// Log the message, code location, expression, error, and captured variables on bail, at the given
+/// [`log::Level`].
+#[cfg(feature = "log")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_bail_with {
+    (
+        [$($target:expr)?],
+        [$($msg:expr)?],
+        $level:expr,
+        $expr:expr,
+        $err:expr
+        $(, $name:ident = $value:expr)*
+    ) => {
+        $crate::__log_backend::log!(
+            $(target: $target,)?
+            $level,
+            concat!(
+                $crate::__msg_prefix!($($msg)?),
+                "Bailed at {}:{}:{}: `{}` is `{:?}`"
+                $(, ", ", stringify!($name), " = `{:?}`")*
+            ),
+            $($msg,)?
+            file!(),
+            line!(),
+            column!(),
+            stringify!($expr),
+            $err,
+            $($value,)*
+        )
+    };
+}
+
+/// Log the message, code location, expression, error, and captured variables on bail, with the
+/// given log backend macro.
+///
+/// Captured variables are also recorded as structured fields.
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_bail_with {
+    (
+        [$($target:expr)?],
+        [$($msg:expr)?],
+        $level:ident,
+        $expr:expr,
+        $err:expr
+        $(, $name:ident = $value:expr)*
+    ) => {
+        $crate::__log_backend::$level!(
+            $(target: $target,)?
+            $($name = ?$value,)*
+            concat!(
+                $crate::__msg_prefix!($($msg)?),
+                "Bailed at {}:{}:{}: `{}` is `{:?}`"
+                $(, ", ", stringify!($name), " = `{:?}`")*
+            ),
+            $($msg,)?
+            file!(),
+            line!(),
+            column!(),
+            stringify!($expr),
+            $err,
+            $($value,)*
+        )
+    };
+}
+
+/// Log the message, code location, expression, error, and captured variables on bail, with the
+/// given log backend macro.
+///
+/// Captured variables are formatted together as a tuple, since `defmt` requires a literal format
+/// string. For the same reason, messages and captured variables each need their own arms.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_bail_with {
+    ($target:tt, [], $level:ident, $expr:expr, $err:expr) => {
+        $crate::__log_backend::$level!(
+            "Bailed at {=str}:{=u32}:{=u32}: `{=str}` is `{}`",
+            file!(),
+            line!(),
+            column!(),
+            stringify!($expr),
+            $crate::__defmt_select!($err),
+        )
+    };
+
+    ($target:tt, [$msg:expr], $level:ident, $expr:expr, $err:expr) => {
+        $crate::__log_backend::$level!(
+            "{=str}: Bailed at {=str}:{=u32}:{=u32}: `{=str}` is `{}`",
+            $msg,
+            file!(),
+            line!(),
+            column!(),
+            stringify!($expr),
+            $crate::__defmt_select!($err),
+        )
+    };
+
+    ($target:tt, [], $level:ident, $expr:expr, $err:expr $(, $name:ident = $value:expr)+) => {
+        $crate::__log_backend::$level!(
+            "Bailed at {=str}:{=u32}:{=u32}: `{=str}` is `{}`, {=str} = `{}`",
+            file!(),
+            line!(),
+            column!(),
+            stringify!($expr),
+            $crate::__defmt_select!($err),
+            stringify!($($name),+),
+            ($($crate::__defmt_select!($value),)+),
+        )
+    };
+
+    (
+        $target:tt,
+        [$msg:expr],
+        $level:ident,
+        $expr:expr,
+        $err:expr
+        $(, $name:ident = $value:expr)+
+    ) => {
+        $crate::__log_backend::$level!(
+            "{=str}: Bailed at {=str}:{=u32}:{=u32}: `{=str}` is `{}`, {=str} = `{}`",
+            $msg,
+            file!(),
+            line!(),
+            column!(),
+            stringify!($expr),
+            $crate::__defmt_select!($err),
+            stringify!($($name),+),
+            ($($crate::__defmt_select!($value),)+),
+        )
+    };
+}
+
+/// Expand to the format string prefix for the message, if there is one.
+#[cfg(any(feature = "log", feature = "tracing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __msg_prefix {
+    () => {
+        ""
+    };
+    ($msg:expr) => {
+        "{}: "
+    };
+}
+
+/// Select a value to format with `defmt`, or a placeholder if it doesn't implement
+/// [`defmt::Format`].
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __defmt_select {
+    ($value:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__defmt::{ViaFormat as _, ViaOpaque as _};
+        (&$crate::__defmt::Wrap(&$value)).__defmt_err()
+    }};
+}
+
+/// Log the message, code location, expression, error, and captured variables on bail, at the given
+/// log level.
+#[cfg(not(any(feature = "log", feature = "tracing", feature = "defmt")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_bail_at {
+    (
+        $target:tt,
+        [$($msg:expr)?],
+        $level:expr,
+        $expr:expr,
+        $err:expr
+        $(, $name:ident = $value:expr)*
+        $(,)?
+    ) => {
+        if const { $crate::STATIC_MAX_LEVEL.__is_static_enabled() } {
+            let level: $crate::Level = $level;
+            if level.__is_static_enabled() {
+                $crate::fallback::__log(
+                    &$crate::__bail_site!($expr $(, $msg)?),
+                    level,
+                    &$err,
+                    &[$((stringify!($name), &$value as &dyn ::core::fmt::Debug)),*],
                 );
-            };
+            }
+        }
+    };
+}
+
+/// Select a captured value to format with [`Debug`](core::fmt::Debug), or a placeholder if it
+/// doesn't implement it.
+///
+/// The `defmt` backend selects its own placeholder, so the value is passed through as-is.
+#[cfg(not(feature = "defmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __debug_select {
+    ($value:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__debug::{ViaDebug as _, ViaOpaque as _};
+        (&$crate::__debug::Wrap(&$value)).__debug_value()
+    }};
+}
+
+/// Select a captured value to format with [`Debug`](core::fmt::Debug), or a placeholder if it
+/// doesn't implement it.
+///
+/// The `defmt` backend selects its own placeholder, so the value is passed through as-is.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __debug_select {
+    ($value:expr) => {
+        $value
+    };
+}
+
+/// Helpers to format a captured value with [`Debug`](core::fmt::Debug), if possible.
+///
+/// Uses autoref specialization to select the value itself if it implements `Debug`, or a fixed
+/// placeholder message otherwise.
+#[doc(hidden)]
+pub mod __debug {
+    /// A wrapper around the captured value.
+    pub struct Wrap<'a, T>(pub &'a T);
+
+    /// A placeholder for captured values that don't implement `Debug`.
+    pub struct Opaque;
+
+    impl core::fmt::Debug for Opaque {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str("<unformattable>")
+        }
+    }
+
+    /// Select the captured value itself if it implements `Debug`.
+    pub trait ViaDebug<'a, T> {
+        fn __debug_value(&self) -> &'a T;
+    }
+
+    impl<'a, T: core::fmt::Debug> ViaDebug<'a, T> for Wrap<'a, T> {
+        fn __debug_value(&self) -> &'a T {
+            self.0
+        }
+    }
+
+    /// Select the placeholder otherwise.
+    pub trait ViaOpaque {
+        fn __debug_value(&self) -> Opaque;
+    }
+
+    impl<T> ViaOpaque for &Wrap<'_, T> {
+        fn __debug_value(&self) -> Opaque {
+            Opaque
+        }
+    }
+}
+
+/// Helpers to format the failure value with `defmt`, if possible.
+///
+/// Uses autoref specialization to select [`defmt::Format`] if the failure value implements it,
+/// or a fixed placeholder message otherwise.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+pub mod __defmt {
+    /// A wrapper around the failure value.
+    pub struct Wrap<'a, T>(pub &'a T);
+
+    /// A placeholder for failure values that don't implement [`defmt::Format`].
+    pub struct Opaque;
+
+    impl defmt::Format for Opaque {
+        fn format(&self, f: defmt::Formatter) {
+            defmt::write!(f, "<unformattable>");
+        }
+    }
+
+    /// Select the failure value itself if it implements [`defmt::Format`].
+    pub trait ViaFormat<'a, T> {
+        fn __defmt_err(&self) -> &'a T;
+    }
+
+    impl<'a, T: defmt::Format> ViaFormat<'a, T> for Wrap<'a, T> {
+        fn __defmt_err(&self) -> &'a T {
+            self.0
+        }
+    }
+
+    /// Select the placeholder otherwise.
+    pub trait ViaOpaque {
+        fn __defmt_err(&self) -> Opaque;
+    }
+
+    impl<T> ViaOpaque for &Wrap<'_, T> {
+        fn __defmt_err(&self) -> Opaque {
+            Opaque
         }
+    }
+}
+
+/// The static code location, expression, and message of a bail.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BailSite {
+    /// The path of the source file, as given by [`file!`].
+    pub file: &'static str,
+    /// The line number, as given by [`line!`].
+    pub line: u32,
+    /// The column number, as given by [`column!`].
+    pub column: u32,
+    /// The stringified expression that failed.
+    pub expr: &'static str,
+    /// The message set with the `msg` option, if any.
+    pub msg: Option<&'static str>,
+}
+
+impl core::fmt::Display for BailSite {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
 
-        // Workaround for <https://github.com/rust-lang/rust/pull/52234>.
-        #[doc(hidden)]
-        pub use ___log_bail as __log_bail;
+/// A helper macro to construct the [`BailSite`] of an expression, with an optional message.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bail_site {
+    ($expr:expr) => {
+        $crate::BailSite {
+            file: file!(),
+            line: line!(),
+            column: column!(),
+            expr: stringify!($expr),
+            msg: ::core::option::Option::None,
+        }
+    };
+    ($expr:expr, $msg:expr) => {
+        $crate::BailSite {
+            file: file!(),
+            line: line!(),
+            column: column!(),
+            expr: stringify!($expr),
+            msg: ::core::option::Option::Some($msg),
+        }
     };
 }
 
-#[cfg(feature = "trace")]
-set_log_level!(trace);
-#[cfg(feature = "debug")]
-set_log_level!(debug);
-#[cfg(feature = "info")]
-set_log_level!(info);
-#[cfg(feature = "warn")]
-set_log_level!(warn);
-#[cfg(feature = "error")]
-set_log_level!(error);
-#[cfg(not(any(
-    feature = "trace",
-    feature = "debug",
-    feature = "info",
-    feature = "warn",
-    feature = "error",
-)))]
-set_log_level!(println);
+// Set the atomic types.
+#[doc(hidden)]
+pub mod __atomic {
+    pub use core::sync::atomic::Ordering;
+
+    #[cfg(not(feature = "portable-atomic"))]
+    pub use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicU32};
+
+    #[cfg(feature = "portable-atomic")]
+    pub use portable_atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicU32};
+}
 
 /// A trait for types that can be separated into success and failure values.
 ///
@@ -253,190 +930,2871 @@ impl<T, E> IntoResult<T, E> for Result<T, E> {
     }
 }
 
-/// A helper macro to unwrap on success, or log the failure and do something else.
+/// A helper macro to parse the arguments of a bail macro.
+///
+/// Splits the arguments on top-level commas into positional arguments and `key = value` options,
+/// followed by the captured variables after a `;`. Commas inside turbofish generics and qualified
+/// paths don't split arguments.
+///
+/// Positional arguments are scanned a few tokens at a time, so that later macros can split them
+/// further. To stay within the recursion limit, the scan has a fixed budget of steps, after which
+/// the remaining arguments are parsed as expressions instead. Labels, closures, `let` patterns, and
+/// option values are always parsed as fragments.
+///
+/// The remaining input is passed twice, so that tokens can be peeked at or forwarded from the copy
+/// with their original spacing.
 #[doc(hidden)]
 #[macro_export]
-macro_rules! __unwrap_or {
-    ($expr:expr, $else:expr) => {
-        match $crate::IntoResult::into_result($expr) {
-            ::core::result::Result::Ok(x) => x,
-            ::core::result::Result::Err(__err) => {
-                $crate::__log_bail!($expr, __err);
-                $else;
-            }
-        }
+macro_rules! __bail_parse {
+    // Start the next argument: captured variables, an option, or a positional argument.
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt $copy:tt ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@cleanup $cfg $pos $opt [] $($rest)*)
     };
-}
-
-/// Unwrap on success, or log the failure and return.
-///
-/// Returns [`Default::default()`] unless an initial argument is provided to return instead.
-#[macro_export]
-macro_rules! or_return {
-    ($return:expr, $expr:expr $(,)?) => {
-        $crate::__unwrap_or!($expr, return $return)
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt $copy:tt) => {
+        $crate::__bail_parse!(@cleanup $cfg $pos $opt [])
     };
-
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or!($expr, return ::core::default::Default::default())
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt $copy:tt , $($rest:tt)*) => {
+        ::core::compile_error!("expected an expression, found an empty argument")
+    };
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt $copy:tt $key:ident = $($rest:tt)*) => {
+        $crate::__bail_parse!(@opts $cfg $pos $opt $key = $($rest)*)
+    };
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt [$l:lifetime $($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@value (@pos $cfg $pos $opt $budget) [$($args)*] $($args)*)
+    };
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt [| $($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@value (@pos $cfg $pos $opt $budget) [$($args)*] $($args)*)
+    };
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt [|| $($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@value (@pos $cfg $pos $opt $budget) [$($args)*] $($args)*)
+    };
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt [move $($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@value (@pos $cfg $pos $opt $budget) [$($args)*] $($args)*)
+    };
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt [let $($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@value (@pos $cfg $pos $opt $budget) [$($args)*] $($args)*)
+    };
+    (@arg $cfg:tt $pos:tt $opt:tt [] $copy:tt $($args:tt)*) => {
+        $crate::__bail_parse!(@frag $cfg $pos $opt $copy $($args)*)
+    };
+    (@arg $cfg:tt $pos:tt $opt:tt $budget:tt $copy:tt $($args:tt)*) => {
+        $crate::__bail_parse!(@munch $cfg $pos $opt $budget [] [] $copy $($args)*)
     };
-}
 
-/// Unwrap on success, or log the failure and continue.
-///
-/// Accepts an optional 'label as the first argument.
-#[macro_export]
-macro_rules! or_continue {
-    ($label:tt, $expr:expr $(,)?) => {
-        $crate::__unwrap_or!($expr, continue $label)
+    // End the positional argument on a top-level comma or semicolon, or at the end.
+    (@munch $cfg:tt $pos:tt $opt:tt $budget:tt [$($cur:tt)+] [] $copy:tt , $($rest:tt)*) => {
+        $crate::__bail_parse!(@got (@pos $cfg $pos $opt $budget) ($($cur)+) , $($rest)*)
+    };
+    (@munch $cfg:tt $pos:tt $opt:tt $budget:tt [$($cur:tt)+] [] $copy:tt ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@got (@pos $cfg $pos $opt $budget) ($($cur)+) ; $($rest)*)
+    };
+    (@munch $cfg:tt $pos:tt $opt:tt $budget:tt [$($cur:tt)+] $depth:tt $copy:tt) => {
+        $crate::__bail_parse!(@got (@pos $cfg $pos $opt $budget) ($($cur)+))
     };
 
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or!($expr, continue)
+    // Parse the positional argument as a fragment instead, once the budget runs out.
+    (@munch $cfg:tt $pos:tt $opt:tt [] [$($cur:tt)*] $depth:tt [$($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@frag $cfg $pos $opt [$($cur)* $($copy)*] $($cur)* $($copy)*)
     };
-}
 
-/// Unwrap on success, or log the failure and break.
-///
-/// Accepts an optional 'label as the first argument.
-#[macro_export]
-macro_rules! or_break {
-    ($label:tt, $expr:expr $(,)?) => {
-        $crate::__unwrap_or!($expr, break $label)
+    // Track the depth of turbofish generics and qualified paths.
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] [$($depth:tt)*]
+        [$x:tt $y:tt $($copy:tt)*] :: < $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch
+            $cfg
+            $pos
+            $opt
+            [$($budget)*]
+            [$($cur)* $x $y]
+            [< $($depth)*]
+            [$($rest)*]
+            $($rest)*
+        )
     };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [] [] [$x:tt $($copy:tt)*]
+        < $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(@munch $cfg $pos $opt [$($budget)*] [$x] [<] [$($rest)*] $($rest)*)
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] [< $($depth:tt)*]
+        [$x:tt $($copy:tt)*] < $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $x] [< < $($depth)*] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] [< $($depth:tt)*]
+        [$x:tt $($copy:tt)*] > $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $x] [$($depth)*] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] [< < $($depth:tt)*]
+        [$x:tt $($copy:tt)*] >> $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $x] [$($depth)*] [$($rest)*] $($rest)*
+        )
+    };
+
+    // Add up to four tokens to the current argument, stopping before the next separator or path.
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt , $($copy:tt)*] $t1:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt ; $($copy:tt)*] $t1:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt :: $($copy:tt)*] $t1:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt $y:tt , $($copy:tt)*] $t1:tt $t2:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1 $t2] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt $y:tt ; $($copy:tt)*] $t1:tt $t2:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1 $t2] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt $y:tt :: $($copy:tt)*] $t1:tt $t2:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1 $t2] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt $y:tt $z:tt , $($copy:tt)*] $t1:tt $t2:tt $t3:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1 $t2 $t3] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt $y:tt $z:tt ; $($copy:tt)*] $t1:tt $t2:tt $t3:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1 $t2 $t3] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] []
+        [$x:tt $y:tt $z:tt :: $($copy:tt)*] $t1:tt $t2:tt $t3:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1 $t2 $t3] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] [] $copy:tt
+        $t1:tt $t2:tt $t3:tt $t4:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $t1 $t2 $t3 $t4] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $cfg:tt $pos:tt $opt:tt [$b:tt $($budget:tt)*] [$($cur:tt)*] $depth:tt $copy:tt
+        $tt:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(
+            @munch $cfg $pos $opt [$($budget)*] [$($cur)* $tt] $depth [$($rest)*] $($rest)*
+        )
+    };
+
+    // Parse the positional argument as a fragment, unless it's a method chain to split into steps.
+    (@frag [chain $($cfg:tt)*] $pos:tt $opt:tt $copy:tt $($args:tt)*) => {
+        ::core::compile_error!(
+            "method chain is too long to split into steps; split it into several bails"
+        )
+    };
+    (@frag $cfg:tt $pos:tt $opt:tt $copy:tt $($args:tt)*) => {
+        $crate::__bail_parse!(@value (@pos $cfg $pos $opt []) $copy $($args)*)
+    };
+
+    // Parse a value as a fragment: nothing, a label, a closure, a pattern, or an expression.
+    (@value $next:tt $copy:tt , $($rest:tt)*) => {
+        $crate::__bail_parse!(@got $next () , $($rest)*)
+    };
+    (@value $next:tt $copy:tt ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@got $next () ; $($rest)*)
+    };
+    (@value $next:tt $copy:tt) => {
+        $crate::__bail_parse!(@got $next ())
+    };
+    (@value $next:tt $copy:tt $l:lifetime => $v:expr $(, $($rest:tt)*)?) => {
+        $crate::__bail_parse!(@got $next ($l => $v) $(, $($rest)*)?)
+    };
+    (@value $next:tt $copy:tt $l:lifetime => $v:expr ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@got $next ($l => $v) ; $($rest)*)
+    };
+    (@value $next:tt $copy:tt $l:lifetime $(, $($rest:tt)*)?) => {
+        $crate::__bail_parse!(@got $next ($l) $(, $($rest)*)?)
+    };
+    (@value $next:tt $copy:tt $l:lifetime ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@got $next ($l) ; $($rest)*)
+    };
+    (@value $next:tt [| $($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@closure $next $($args)*)
+    };
+    (@value $next:tt [|| $($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@closure $next $($args)*)
+    };
+    (@value $next:tt [move $($copy:tt)*] $($args:tt)*) => {
+        $crate::__bail_parse!(@closure $next $($args)*)
+    };
+    (@value $next:tt $copy:tt let $p:pat = $e:expr $(, $($rest:tt)*)?) => {
+        $crate::__bail_parse!(@got $next (let $p = $e) $(, $($rest)*)?)
+    };
+    (@value $next:tt $copy:tt let $p:pat = $e:expr ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@got $next (let $p = $e) ; $($rest)*)
+    };
+    (@value $next:tt [$($copy:tt)*] $e:expr => $($rest:tt)*) => {
+        $crate::__bail_parse!(@pattern $next $($copy)*)
+    };
+    (@value $next:tt $copy:tt $e:expr $(, $($rest:tt)*)?) => {
+        $crate::__bail_parse!(@got $next ($e) $(, $($rest)*)?)
+    };
+    (@value $next:tt $copy:tt $e:expr ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@got $next ($e) ; $($rest)*)
+    };
+
+    // Tag closures, so that they're called with the failure value.
+    (@closure $next:tt $f:expr $(, $($rest:tt)*)?) => {
+        $crate::__bail_parse!(@got $next (@closure $f) $(, $($rest)*)?)
+    };
+    (@closure $next:tt $f:expr ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@got $next (@closure $f) ; $($rest)*)
+    };
+
+    // Parse a value followed by `=>` as a pattern match.
+    (@pattern $next:tt $p:pat = $e:expr => $v:expr $(, $($rest:tt)*)?) => {
+        $crate::__bail_parse!(@got $next ($p = $e => $v) $(, $($rest)*)?)
+    };
+    (@pattern $next:tt $p:pat = $e:expr => $v:expr ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@got $next ($p = $e => $v) ; $($rest)*)
+    };
+
+    // Push a positional argument, and move on to the next argument.
+    (@got (@pos $cfg:tt $pos:tt $opt:tt $budget:tt) () $($rest:tt)*) => {
+        ::core::compile_error!("expected an expression, found an empty argument")
+    };
+    (@got (@pos $cfg:tt [$($pos:tt)*] $opt:tt $budget:tt) $value:tt , $($rest:tt)*) => {
+        $crate::__bail_parse!(@arg $cfg [$($pos)* $value] $opt $budget [$($rest)*] $($rest)*)
+    };
+    (@got (@pos $cfg:tt [$($pos:tt)*] $opt:tt $budget:tt) $value:tt $($rest:tt)*) => {
+        $crate::__bail_parse!(@arg $cfg [$($pos)* $value] $opt $budget [$($rest)*] $($rest)*)
+    };
+
+    // Push the value of the `on_fail` option, and move on to the next option.
+    (@got (@on_fail $cfg:tt $pos:tt [$($opt:tt)*] $kind:ident) ($($value:tt)*) $($rest:tt)*) => {
+        $crate::__bail_parse!(@opts $cfg $pos [$($opt)* on_fail ($kind $($value)*)] $($rest)*)
+    };
+
+    // Parse the options, all of which take a value.
+    (@opts $cfg:tt $pos:tt $opt:tt , $($rest:tt)*) => {
+        $crate::__bail_parse!(@opts $cfg $pos $opt $($rest)*)
+    };
+    (@opts $cfg:tt $pos:tt $opt:tt ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@cleanup $cfg $pos $opt [] $($rest)*)
+    };
+    (@opts $cfg:tt $pos:tt $opt:tt) => {
+        $crate::__bail_parse!(@cleanup $cfg $pos $opt [])
+    };
+    (
+        @opts $cfg:tt $pos:tt [$($opt:tt)*]
+        on_fail = $kind:ident $l:lifetime $v:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::__bail_parse!(@opts $cfg $pos [$($opt)* on_fail ($kind $l $v)] $($($rest)*)?)
+    };
+    (
+        @opts $cfg:tt $pos:tt [$($opt:tt)*]
+        on_fail = $kind:ident $l:lifetime $v:expr ; $($rest:tt)*
+    ) => {
+        $crate::__bail_parse!(@cleanup $cfg $pos [$($opt)* on_fail ($kind $l $v)] [] $($rest)*)
+    };
+    (@opts $cfg:tt $pos:tt $opt:tt on_fail = $kind:ident $($rest:tt)*) => {
+        $crate::__bail_parse!(@value (@on_fail $cfg $pos $opt $kind) [$($rest)*] $($rest)*)
+    };
+    (@opts $cfg:tt $pos:tt [$($opt:tt)*] $key:ident = $value:ident $(, $($rest:tt)*)?) => {
+        $crate::__bail_parse!(@opts $cfg $pos [$($opt)* $key ($value)] $($($rest)*)?)
+    };
+    (@opts $cfg:tt $pos:tt [$($opt:tt)*] $key:ident = $value:ident ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@cleanup $cfg $pos [$($opt)* $key ($value)] [] $($rest)*)
+    };
+    (@opts $cfg:tt $pos:tt [$($opt:tt)*] $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__bail_parse!(@opts $cfg $pos [$($opt)* $key ($value)] $($($rest)*)?)
+    };
+    (@opts $cfg:tt $pos:tt [$($opt:tt)*] $key:ident = $value:expr ; $($rest:tt)*) => {
+        $crate::__bail_parse!(@cleanup $cfg $pos [$($opt)* $key ($value)] [] $($rest)*)
+    };
+    (@opts $cfg:tt $pos:tt $opt:tt $($rest:tt)+) => {
+        ::core::compile_error!(
+            "expected `key = value` options after the positional arguments, \
+             followed by `; captured, variables`"
+        )
+    };
+
+    // Split off the cleanup block at the end of the captured variables, if any.
+    (@cleanup $cfg:tt $pos:tt [$($opt:tt)*] $capture:tt else { $($cleanup:tt)* }) => {
+        $crate::__bail_opts!(
+            @munch
+            [$cfg $pos $capture]
+            [
+                quiet_if []
+                attempts []
+                backoff []
+                level []
+                msg []
+                target []
+                cleanup [{ $($cleanup)* }]
+            ]
+            $($opt)*
+        )
+    };
+    (@cleanup $cfg:tt $pos:tt $opt:tt [$($capture:tt)*] $tt:tt $($rest:tt)*) => {
+        $crate::__bail_parse!(@cleanup $cfg $pos $opt [$($capture)* $tt] $($rest)*)
+    };
+    (@cleanup $cfg:tt $pos:tt [$($opt:tt)*] $capture:tt) => {
+        $crate::__bail_opts!(
+            @munch
+            [$cfg $pos $capture]
+            [quiet_if [] attempts [] backoff [] level [] msg [] target [] cleanup []]
+            $($opt)*
+        )
+    };
+
+    // Start parsing, given preset options.
+    (@opt [$($opt:tt)*] [$($cfg:tt)+] $($args:tt)*) => {
+        $crate::__bail_parse!(
+            @arg
+            [$($cfg)+]
+            []
+            [$($opt)*]
+            [_ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _]
+            [$($args)*]
+            $($args)*
+        )
+    };
+
+    // Start parsing, given the bail kind and log mode.
+    ([$($cfg:tt)+] $($args:tt)*) => {
+        $crate::__bail_parse!(@opt [] [$($cfg)+] $($args)*)
+    };
+}
+
+/// A helper macro to collect the parsed options of a bail macro by key.
+///
+/// Each option is stored as an empty list if unset, so that later macros can match them by
+/// position.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bail_opts {
+    // Set the bail action and log mode of `bail!`.
+    (
+        @munch [[bail [] $mode:tt] $pos:tt $capture:tt] $opt:tt on_fail ($($value:tt)+)
+        $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(@on_fail [$mode $pos $capture] $opt [$($value)+] $($rest)*)
+    };
+    (@munch [[bail $kind:tt []] $pos:tt $capture:tt] $opt:tt log (always) $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail $kind [log]] $pos $capture] $opt $($rest)*)
+    };
+    (@munch [[bail $kind:tt []] $pos:tt $capture:tt] $opt:tt log (quiet) $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail $kind [quiet]] $pos $capture] $opt $($rest)*)
+    };
+    (@munch [[bail $kind:tt []] $pos:tt $capture:tt] $opt:tt log (once) $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail $kind [once]] $pos $capture] $opt $($rest)*)
+    };
+    (@munch [[bail $kind:tt []] $pos:tt $capture:tt] $opt:tt log (escalate) $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail $kind [escalate]] $pos $capture] $opt $($rest)*)
+    };
+    (@munch [[bail $kind:tt []] $pos:tt $capture:tt] $opt:tt log $value:tt $($rest:tt)*) => {
+        ::core::compile_error!("expected `log = always`, `quiet`, `once`, or `escalate`")
+    };
+
+    // Prepend the value of the bail action, if any, to the positional arguments.
+    (@on_fail [$mode:tt $pos:tt $capture:tt] $opt:tt [return] $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail [return] $mode] $pos $capture] $opt $($rest)*)
+    };
+    (@on_fail [$mode:tt [$($pos:tt)*] $capture:tt] $opt:tt [return $($value:tt)+] $($rest:tt)*) => {
+        $crate::__bail_opts!(
+            @munch [[bail [return] $mode] [($($value)+) $($pos)*] $capture] $opt $($rest)*
+        )
+    };
+    (@on_fail [$mode:tt $pos:tt $capture:tt] $opt:tt [continue] $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail [continue] $mode] $pos $capture] $opt $($rest)*)
+    };
+    (
+        @on_fail [$mode:tt [$($pos:tt)*] $capture:tt] $opt:tt [continue $label:lifetime]
+        $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch [[bail [continue] $mode] [($label) $($pos)*] $capture] $opt $($rest)*
+        )
+    };
+    (@on_fail [$mode:tt $pos:tt $capture:tt] $opt:tt [break] $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail [break] $mode] $pos $capture] $opt $($rest)*)
+    };
+    (
+        @on_fail [$mode:tt [$($pos:tt)*] $capture:tt] $opt:tt [break $label:lifetime]
+        $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch [[bail [break] $mode] [($label) $($pos)*] $capture] $opt $($rest)*
+        )
+    };
+    (
+        @on_fail [$mode:tt [$($pos:tt)*] $capture:tt] $opt:tt
+        [break $label:lifetime => $($value:tt)+]
+        $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch [[bail [break] $mode] [($label => $($value)+) $($pos)*] $capture] $opt $($rest)*
+        )
+    };
+    (
+        @on_fail [$mode:tt [$($pos:tt)*] $capture:tt] $opt:tt
+        [break $label:lifetime $($value:tt)+]
+        $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch [[bail [break] $mode] [($label => $($value)+) $($pos)*] $capture] $opt $($rest)*
+        )
+    };
+    (@on_fail [$mode:tt $pos:tt $capture:tt] $opt:tt [default] $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail [or] $mode] $pos $capture] $opt $($rest)*)
+    };
+    (
+        @on_fail [$mode:tt [$($pos:tt)*] $capture:tt] $opt:tt [default $($value:tt)+]
+        $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch [[bail [or] $mode] [($($value)+) $($pos)*] $capture] $opt $($rest)*
+        )
+    };
+    (@on_fail [$mode:tt $pos:tt $capture:tt] $opt:tt [exit] $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail [exit] $mode] $pos $capture] $opt $($rest)*)
+    };
+    (@on_fail [$mode:tt [$($pos:tt)*] $capture:tt] $opt:tt [exit $($code:tt)+] $($rest:tt)*) => {
+        $crate::__bail_opts!(
+            @munch [[bail [exit] $mode] [($($code)+) $($pos)*] $capture] $opt $($rest)*
+        )
+    };
+    (@on_fail [$mode:tt $pos:tt $capture:tt] $opt:tt [err] $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch [[bail [err] $mode] $pos $capture] $opt $($rest)*)
+    };
+    (@on_fail [$mode:tt [$($pos:tt)*] $capture:tt] $opt:tt [err $($context:tt)+] $($rest:tt)*) => {
+        $crate::__bail_opts!(
+            @munch [[bail [err] $mode] [($($context)+) $($pos)*] $capture] $opt $($rest)*
+        )
+    };
+    (@on_fail $next:tt $opt:tt $value:tt $($rest:tt)*) => {
+        ::core::compile_error!(
+            "expected `on_fail = return`, `continue`, `break`, `default`, `exit`, or `err`, \
+             optionally followed by a value"
+        )
+    };
+
+    // Set an option if it hasn't been set yet.
+    (
+        @munch $next:tt [quiet_if [] $($other:tt)*]
+        quiet_if ($($value:tt)+) $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(@munch $next [quiet_if [($($value)+)] $($other)*] $($rest)*)
+    };
+    (
+        @munch $next:tt [quiet_if $quiet_if:tt attempts [] $($other:tt)*]
+        attempts ($($value:tt)+) $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch $next [quiet_if $quiet_if attempts [($($value)+)] $($other)*] $($rest)*
+        )
+    };
+    (
+        @munch $next:tt [quiet_if $quiet_if:tt attempts $attempts:tt backoff [] $($other:tt)*]
+        backoff ($($value:tt)+) $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch
+            $next
+            [quiet_if $quiet_if attempts $attempts backoff [($($value)+)] $($other)*]
+            $($rest)*
+        )
+    };
+    (
+        @munch
+        $next:tt
+        [quiet_if $quiet_if:tt attempts $attempts:tt backoff $backoff:tt level [] $($other:tt)*]
+        level ($($value:tt)+) $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch
+            $next
+            [
+                quiet_if $quiet_if
+                attempts $attempts
+                backoff $backoff
+                level [($crate::__bail_opts!(@level $($value)+))]
+                $($other)*
+            ]
+            $($rest)*
+        )
+    };
+    (
+        @munch
+        $next:tt
+        [
+            quiet_if $quiet_if:tt
+            attempts $attempts:tt
+            backoff $backoff:tt
+            level $level:tt
+            msg []
+            $($other:tt)*
+        ]
+        msg ($($value:tt)+) $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch
+            $next
+            [
+                quiet_if $quiet_if
+                attempts $attempts
+                backoff $backoff
+                level $level
+                msg [($($value)+)]
+                $($other)*
+            ]
+            $($rest)*
+        )
+    };
+
+    (
+        @munch
+        $next:tt
+        [
+            quiet_if $quiet_if:tt
+            attempts $attempts:tt
+            backoff $backoff:tt
+            level $level:tt
+            msg $msg:tt
+            target []
+            $($other:tt)*
+        ]
+        target ($($value:tt)+) $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch
+            $next
+            [
+                quiet_if $quiet_if
+                attempts $attempts
+                backoff $backoff
+                level $level
+                msg $msg
+                target [($($value)+)]
+                $($other)*
+            ]
+            $($rest)*
+        )
+    };
+
+    // Move default options to the end, and only set them if they're still unset there.
+    (@munch $next:tt $opt:tt @default $key:ident $value:tt $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch $next $opt $($rest)* @unset $key $value)
+    };
+    (
+        @munch
+        $next:tt
+        [quiet_if $quiet_if:tt attempts $attempts:tt backoff $backoff:tt level [] $($other:tt)*]
+        @unset level $value:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch
+            $next
+            [quiet_if $quiet_if attempts $attempts backoff $backoff level [] $($other)*]
+            level $value $($rest)*
+        )
+    };
+    (
+        @munch
+        $next:tt
+        [
+            quiet_if $quiet_if:tt
+            attempts $attempts:tt
+            backoff $backoff:tt
+            level $level:tt
+            msg []
+            $($other:tt)*
+        ]
+        @unset msg $value:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch
+            $next
+            [quiet_if $quiet_if attempts $attempts backoff $backoff level $level msg [] $($other)*]
+            msg $value $($rest)*
+        )
+    };
+    (
+        @munch
+        $next:tt
+        [
+            quiet_if $quiet_if:tt
+            attempts $attempts:tt
+            backoff $backoff:tt
+            level $level:tt
+            msg $msg:tt
+            target []
+            $($other:tt)*
+        ]
+        @unset target $value:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_opts!(
+            @munch
+            $next
+            [
+                quiet_if $quiet_if
+                attempts $attempts
+                backoff $backoff
+                level $level
+                msg $msg
+                target []
+                $($other)*
+            ]
+            target $value $($rest)*
+        )
+    };
+    (@munch $next:tt $opt:tt @unset $key:ident $value:tt $($rest:tt)*) => {
+        $crate::__bail_opts!(@munch $next $opt $($rest)*)
+    };
+
+    // Accept log level names as a shorthand.
+    (@level off) => { $crate::Level::Off };
+    (@level error) => { $crate::Level::Error };
+    (@level warn) => { $crate::Level::Warn };
+    (@level info) => { $crate::Level::Info };
+    (@level debug) => { $crate::Level::Debug };
+    (@level trace) => { $crate::Level::Trace };
+    (@level $($level:tt)+) => { $($level)+ };
+
+    // Reject duplicate and unsupported options.
+    (@munch $next:tt $opt:tt $key:ident ($($value:tt)+) $($rest:tt)*) => {
+        $crate::__bail_opts!(@reject $next $key ($($value)+))
+    };
+    (@reject [[bail $($cfg:tt)*] $($next:tt)*] on_fail $value:tt) => {
+        ::core::compile_error!("duplicate bail option: `on_fail`")
+    };
+    (@reject [[bail $($cfg:tt)*] $($next:tt)*] log $value:tt) => {
+        ::core::compile_error!("duplicate bail option: `log`")
+    };
+    (@reject $next:tt quiet_if $value:tt) => {
+        ::core::compile_error!("duplicate bail option: `quiet_if`")
+    };
+    (@reject $next:tt attempts $value:tt) => {
+        ::core::compile_error!("duplicate bail option: `attempts`")
+    };
+    (@reject $next:tt backoff $value:tt) => {
+        ::core::compile_error!("duplicate bail option: `backoff`")
+    };
+    (@reject $next:tt level $value:tt) => {
+        ::core::compile_error!("duplicate bail option: `level`")
+    };
+    (@reject $next:tt msg $value:tt) => {
+        ::core::compile_error!("duplicate bail option: `msg`")
+    };
+    (@reject $next:tt target $value:tt) => {
+        ::core::compile_error!("duplicate bail option: `target`")
+    };
+    (@reject $next:tt $key:ident ($($value:tt)+)) => {
+        ::core::compile_error!(::core::concat!(
+            "unsupported bail option: `",
+            ::core::stringify!($key = $($value)+),
+            "`",
+        ))
+    };
+
+    // Finish, unless `backoff` is set without `attempts`.
+    (@munch $next:tt [quiet_if $quiet_if:tt attempts [] backoff [$backoff:tt] $($other:tt)*]) => {
+        ::core::compile_error!("the `backoff` bail option requires `attempts`")
+    };
+    (@munch [[bail [] $mode:tt] $pos:tt $capture:tt] $opt:tt) => {
+        $crate::__bail_opts!(@munch [[bail [return] $mode] $pos $capture] $opt)
+    };
+    (@munch [[bail $kind:tt []] $pos:tt $capture:tt] $opt:tt) => {
+        $crate::__bail_opts!(@munch [[bail $kind [log]] $pos $capture] $opt)
+    };
+    (@munch [[bail [$kind:tt] [$mode:ident]] $pos:tt $capture:tt] $opt:tt) => {
+        $crate::__bail_opts!(@munch [[expr $kind $mode] $pos $capture] $opt)
+    };
+    (@munch [$cfg:tt $pos:tt $capture:tt] $opt:tt) => {
+        $crate::__bail_dispatch!($cfg $pos $opt $capture)
+    };
+}
+
+/// A helper macro to interpret the parsed arguments of a bail macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bail_dispatch {
+    ([all return $mode:ident] [$($expr:tt)+] $opt:tt [$($capture:ident),* $(,)?]) => {
+        $crate::__bail_dispatch!(
+            @via all
+            ($mode { return ::core::default::Default::default() } $opt [$($capture = $capture),*])
+            $($expr)+
+        )
+    };
+    (
+        [all break $mode:ident]
+        [($label:lifetime => $($value:tt)+) $($expr:tt)+]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via all ($mode { break $label $($value)+ } $opt [$($capture = $capture),*]) $($expr)+
+        )
+    };
+    (
+        [all $kind:tt $mode:ident]
+        [($label:lifetime) $($expr:tt)+]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via all ($mode { $kind $label } $opt [$($capture = $capture),*]) $($expr)+
+        )
+    };
+    ([all $kind:tt $mode:ident] [$($expr:tt)+] $opt:tt [$($capture:ident),* $(,)?]) => {
+        $crate::__bail_dispatch!(
+            @via all ($mode { $kind } $opt [$($capture = $capture),*]) $($expr)+
+        )
+    };
+    ([all $kind:tt $mode:ident] $pos:tt $opt:tt $capture:tt) => {
+        ::core::compile_error!(
+            "expected expressions to unwrap, followed by options and `; captured, variables`"
+        )
+    };
+
+    (
+        [$via:ident return $mode:ident]
+        [(@closure $return:tt) ($($expr:tt)+)]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            ($mode { |__err| return $return(__err) } $opt [$($capture = $capture),*])
+            $($expr)+
+        )
+    };
+    (
+        [$via:ident return $mode:ident]
+        [($($return:tt)+) ($($expr:tt)+)]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via $via ($mode { return $($return)+ } $opt [$($capture = $capture),*]) $($expr)+
+        )
+    };
+    ([$via:ident return $mode:ident] [($($expr:tt)+)] $opt:tt [$($capture:ident),* $(,)?]) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            ($mode { return ::core::default::Default::default() } $opt [$($capture = $capture),*])
+            $($expr)+
+        )
+    };
+    ([$via:ident return $mode:ident] $pos:tt $opt:tt $capture:tt) => {
+        ::core::compile_error!(
+            "expected an expression to unwrap, optionally preceded by a value to return, \
+             and followed by options and `; captured, variables`"
+        )
+    };
+
+    (
+        [$via:ident or $mode:ident]
+        [(@closure $fallback:tt) ($($expr:tt)+)]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            ($mode { |__err| $fallback(__err) } $opt [$($capture = $capture),*])
+            $($expr)+
+        )
+    };
+    (
+        [$via:ident or $mode:ident]
+        [($($fallback:tt)+) ($($expr:tt)+)]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via $via ($mode { $($fallback)+ } $opt [$($capture = $capture),*]) $($expr)+
+        )
+    };
+    ([$via:ident or $mode:ident] [($($expr:tt)+)] $opt:tt [$($capture:ident),* $(,)?]) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            ($mode { ::core::default::Default::default() } $opt [$($capture = $capture),*])
+            $($expr)+
+        )
+    };
+    ([$via:ident or $mode:ident] $pos:tt $opt:tt $capture:tt) => {
+        ::core::compile_error!(
+            "expected an expression to unwrap, optionally preceded by a fallback value, \
+             and followed by options and `; captured, variables`"
+        )
+    };
+
+    (
+        [$via:ident exit $mode:ident]
+        [($($code:tt)+) ($($expr:tt)+)]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            (
+                $mode
+                {
+                    |__err, __site| {
+                        ::std::eprintln!("{}", $crate::__exit::ExitMessage {
+                            site: &__site,
+                            err: &__err,
+                            location: $crate::__exit::__exit_location(),
+                        });
+                        ::std::process::exit($($code)+)
+                    }
+                }
+                $opt
+                [$($capture = $capture),*]
+            )
+            $($expr)+
+        )
+    };
+    ([$via:ident exit $mode:ident] [($($expr:tt)+)] $opt:tt $capture:tt) => {
+        $crate::__bail_dispatch!([$via exit $mode] [(1) ($($expr)+)] $opt $capture)
+    };
+    ([$via:ident exit $mode:ident] $pos:tt $opt:tt $capture:tt) => {
+        ::core::compile_error!(
+            "expected an expression to unwrap, optionally preceded by an exit code, \
+             and followed by options and `; captured, variables`"
+        )
+    };
+
+    ([$via:ident panic $mode:ident] [($($expr:tt)+)] $opt:tt [$($capture:ident),* $(,)?]) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            (
+                $mode
+                {
+                    |__err, __site| {
+                        #[allow(unused_imports)]
+                        use $crate::__debug::{ViaDebug as _, ViaOpaque as _};
+                        ::core::panic!(
+                            "{}{}Bailed at {}: `{}` is `{:?}`",
+                            __site.msg.unwrap_or(""),
+                            if __site.msg.is_some() { ": " } else { "" },
+                            __site,
+                            __site.expr,
+                            (&$crate::__debug::Wrap(&__err)).__debug_value(),
+                        )
+                    }
+                }
+                $opt
+                [$($capture = $capture),*]
+            )
+            $($expr)+
+        )
+    };
+    // Ignore the value, 'label, or closure of the bail action that's replaced by the panic.
+    ([$via:ident panic $mode:ident] [$action:tt ($($expr:tt)+)] $opt:tt $capture:tt) => {
+        $crate::__bail_dispatch!([$via panic $mode] [($($expr)+)] $opt $capture)
+    };
+    ([$via:ident panic $mode:ident] $pos:tt $opt:tt $capture:tt) => {
+        ::core::compile_error!(
+            "expected an expression to unwrap, followed by options and `; captured, variables`"
+        )
+    };
+
+    ([$via:ident bailed $mode:ident] [($($expr:tt)+)] $opt:tt [$($capture:ident),* $(,)?]) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            (
+                $mode
+                {
+                    |__err, __site| return ::core::result::Result::Err(
+                        ::core::convert::From::from({
+                            #[allow(unused_imports)]
+                            use $crate::__bailed::{ViaError as _, ViaOpaque as _};
+                            $crate::__bailed::Own(__err).__bailed(__site)
+                        })
+                    )
+                }
+                $opt
+                [$($capture = $capture),*]
+            )
+            $($expr)+
+        )
+    };
+    ([$via:ident bailed $mode:ident] $pos:tt $opt:tt $capture:tt) => {
+        ::core::compile_error!(
+            "expected an expression to unwrap, followed by options and `; captured, variables`"
+        )
+    };
+
+    (
+        [$via:ident err $mode:ident]
+        [(@closure $context:tt) ($($expr:tt)+)]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            (
+                $mode
+                {
+                    |__err| return ::core::result::Result::Err(
+                        ::core::convert::From::from($context(__err))
+                    )
+                }
+                $opt
+                [$($capture = $capture),*]
+            )
+            $($expr)+
+        )
+    };
+    ([$via:ident err $mode:ident] [($($expr:tt)+)] $opt:tt [$($capture:ident),* $(,)?]) => {
+        $crate::__bail_dispatch!(
+            @via $via
+            (
+                $mode
+                { |__err| return ::core::result::Result::Err(::core::convert::From::from(__err)) }
+                $opt
+                [$($capture = $capture),*]
+            )
+            $($expr)+
+        )
+    };
+    ([$via:ident err $mode:ident] $pos:tt $opt:tt $capture:tt) => {
+        ::core::compile_error!(
+            "expected an expression to unwrap, optionally preceded by a closure to wrap the error, \
+             and followed by options and `; captured, variables`"
+        )
+    };
+
+    (
+        [$via:ident break $mode:ident]
+        [($label:lifetime => $($value:tt)+) ($($expr:tt)+)]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via $via ($mode { break $label $($value)+ } $opt [$($capture = $capture),*]) $($expr)+
+        )
+    };
+    (
+        [$via:ident $kind:tt $mode:ident]
+        [($label:lifetime) ($($expr:tt)+)]
+        $opt:tt
+        [$($capture:ident),* $(,)?]
+    ) => {
+        $crate::__bail_dispatch!(
+            @via $via ($mode { $kind $label } $opt [$($capture = $capture),*]) $($expr)+
+        )
+    };
+    ([$via:ident $kind:tt $mode:ident] [($($expr:tt)+)] $opt:tt [$($capture:ident),* $(,)?]) => {
+        $crate::__bail_dispatch!(
+            @via $via ($mode { $kind } $opt [$($capture = $capture),*]) $($expr)+
+        )
+    };
+    ([$via:ident $kind:tt $mode:ident] $pos:tt $opt:tt $capture:tt) => {
+        ::core::compile_error!(
+            "expected an expression to unwrap, optionally preceded by a 'label, \
+             and followed by options and `; captured, variables`"
+        )
+    };
+
+    // Run the cleanup block, if any, after binding the failure value and before the bail action.
+    (
+        @via $via:ident
+        (
+            $mode:ident
+            { |$err:ident, $site:ident| $($else:tt)* }
+            [
+                quiet_if $quiet_if:tt
+                attempts $attempts:tt
+                backoff $backoff:tt
+                level $level:tt
+                msg $msg:tt
+                target $target:tt
+                cleanup [$($cleanup:tt)?]
+            ]
+            $capture:tt
+        )
+        $($expr:tt)+
+    ) => {
+        $crate::__bail_dispatch!(
+            @handle $via
+            (
+                $mode
+                { |$err, $site| $($cleanup)? $($else)* }
+                [
+                    quiet_if $quiet_if
+                    attempts $attempts
+                    backoff $backoff
+                    level $level
+                    msg $msg
+                    target $target
+                ]
+                $capture
+            )
+            $($expr)+
+        )
+    };
+    (
+        @via $via:ident
+        (
+            $mode:ident
+            { |$err:ident| $($else:tt)* }
+            [
+                quiet_if $quiet_if:tt
+                attempts $attempts:tt
+                backoff $backoff:tt
+                level $level:tt
+                msg $msg:tt
+                target $target:tt
+                cleanup [$($cleanup:tt)?]
+            ]
+            $capture:tt
+        )
+        $($expr:tt)+
+    ) => {
+        $crate::__bail_dispatch!(
+            @handle $via
+            (
+                $mode
+                { |$err| $($cleanup)? $($else)* }
+                [
+                    quiet_if $quiet_if
+                    attempts $attempts
+                    backoff $backoff
+                    level $level
+                    msg $msg
+                    target $target
+                ]
+                $capture
+            )
+            $($expr)+
+        )
+    };
+    (
+        @via $via:ident
+        (
+            $mode:ident
+            { $($else:tt)* }
+            [
+                quiet_if $quiet_if:tt
+                attempts $attempts:tt
+                backoff $backoff:tt
+                level $level:tt
+                msg $msg:tt
+                target $target:tt
+                cleanup [$($cleanup:tt)?]
+            ]
+            $capture:tt
+        )
+        $($expr:tt)+
+    ) => {
+        $crate::__bail_dispatch!(
+            @handle $via
+            (
+                $mode
+                { $($cleanup)? $($else)* }
+                [
+                    quiet_if $quiet_if
+                    attempts $attempts
+                    backoff $backoff
+                    level $level
+                    msg $msg
+                    target $target
+                ]
+                $capture
+            )
+            $($expr)+
+        )
+    };
+
+    // Unwrap the expression as a whole, step by step, or as several expressions.
+    (@handle expr $ctx:tt $($expr:tt)+) => {
+        $crate::__bail_expr!($ctx $($expr)+)
+    };
+    (@handle chain $ctx:tt $($expr:tt)+) => {
+        $crate::__bail_chain!($ctx $($expr)+)
+    };
+    (@handle all $ctx:tt $($expr:tt)+) => {
+        $crate::__bail_all!($ctx [0usize] [] $($expr)+)
+    };
+}
+
+/// A helper macro to unwrap each `?`-terminated step of a method chain, bailing on the first
+/// failure.
+///
+/// Each step is logged as the chain up to and including that step. Any steps after the last `?`
+/// are applied to the unwrapped value as-is. Like `__bail_parse!`, the remaining input is passed
+/// twice to preserve the original spacing, and runs of tokens without a `?` are taken in one step.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bail_chain {
+    // Split off the current step on a top-level `?`.
+    (
+        @munch $ctx:tt [$($step:tt)*] [$($prefix:tt)*] [$($cur:tt)+] [$q:tt $($orig:tt)*]
+        ? $($rest:tt)*
+    ) => {
+        $crate::__bail_chain!(
+            @munch
+            $ctx
+            [$($step)* [($($prefix)* $($cur)+) ($($cur)+)]]
+            [$($prefix)* $($cur)+ $q]
+            []
+            [$($rest)*]
+            $($rest)*
+        )
+    };
+
+    // Finish with the unwrapped steps, followed by any infallible steps.
+    (@munch $ctx:tt [] $prefix:tt $cur:tt $orig:tt) => {
+        ::core::compile_error!("expected at least one `?` in the chain")
+    };
+    (@munch $ctx:tt [$($step:tt)+] $prefix:tt [$($cur:tt)*] $orig:tt) => {
+        $crate::__bail_chain!(@emit $ctx [$($cur)*] $($step)+)
+    };
+
+    // Add tokens to the current step, up to the next `?`.
+    (
+        @munch $ctx:tt $step:tt $prefix:tt [$($cur:tt)*] [$x:tt ? $($orig:tt)*]
+        $t1:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_chain!(@munch $ctx $step $prefix [$($cur)* $t1] [$($rest)*] $($rest)*)
+    };
+    (
+        @munch $ctx:tt $step:tt $prefix:tt [$($cur:tt)*] [$x:tt $y:tt ? $($orig:tt)*]
+        $t1:tt $t2:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_chain!(@munch $ctx $step $prefix [$($cur)* $t1 $t2] [$($rest)*] $($rest)*)
+    };
+    (
+        @munch $ctx:tt $step:tt $prefix:tt [$($cur:tt)*] [$x:tt $y:tt $z:tt ? $($orig:tt)*]
+        $t1:tt $t2:tt $t3:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_chain!(
+            @munch $ctx $step $prefix [$($cur)* $t1 $t2 $t3] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $ctx:tt $step:tt $prefix:tt [$($cur:tt)*] [$x:tt $y:tt $z:tt $w:tt $($orig:tt)*]
+        $t1:tt $t2:tt $t3:tt $t4:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_chain!(
+            @munch $ctx $step $prefix [$($cur)* $t1 $t2 $t3 $t4] [$($rest)*] $($rest)*
+        )
+    };
+    (@munch $ctx:tt $step:tt $prefix:tt [$($cur:tt)*] $orig:tt $tt:tt $($rest:tt)*) => {
+        $crate::__bail_chain!(@munch $ctx $step $prefix [$($cur)* $tt] [$($rest)*] $($rest)*)
+    };
+
+    // Unwrap each step in turn, continuing from the previous step's value.
+    (
+        @emit $ctx:tt [$($tail:tt)*]
+        [($($first_expr:tt)+) ($($first:tt)+)]
+        $([($($expr:tt)+) ($($next:tt)+)])*
+    ) => {{
+        let __chain = $crate::__unwrap_or!($ctx, $($first_expr)+, $($first)+);
+        $(let __chain = $crate::__unwrap_or!($ctx, $($expr)+, __chain $($next)+);)*
+        __chain $($tail)*
+    }};
+
+    // Start splitting the chain into steps.
+    ($ctx:tt $($expr:tt)+) => {
+        $crate::__bail_chain!(@munch $ctx [] [] [] [$($expr)+] $($expr)+)
+    };
+}
+
+/// A helper macro to unwrap several expressions into a tuple, bailing on the first failure.
+///
+/// The index of each expression is captured into the bail message.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bail_all {
+    (
+        ($mode:ident $else:tt $opt:tt [$($capture:tt)*])
+        [$($index:tt)+]
+        [$($out:tt)*]
+        ($($expr:tt)+)
+        $($rest:tt)*
+    ) => {
+        $crate::__bail_all!(
+            ($mode $else $opt [$($capture)*])
+            [$($index)+ + 1]
+            [
+                $($out)*
+                $crate::__bail_expr!(
+                    ($mode $else $opt [index = $($index)+, $($capture)*]) $($expr)+
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    ($ctx:tt $index:tt [$($out:tt)*]) => {
+        ($($out)*)
+    };
+}
+
+/// A helper macro to classify the expression to unwrap as a pattern match, a comparison, or a plain
+/// expression.
+///
+/// Comparisons are split into their operands, so that they can be captured into the bail message.
+/// Like `__bail_parse!`, the remaining input is passed twice to preserve the original spacing, and
+/// the scan has a fixed budget of steps. Past the budget, the expression is unwrapped as a pattern
+/// match or a plain expression.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bail_expr {
+    // Classify expressions that start with a keyword.
+    (@munch $ctx:tt $budget:tt [$($all:tt)+] [] [] [] $orig:tt let $($rest:tt)*) => {
+        $crate::__unwrap_or!($ctx $($all)+)
+    };
+    (@munch $ctx:tt $budget:tt $all:tt [] [] [] $orig:tt if $($rest:tt)*) => {
+        $crate::__bail_expr!(@plain $ctx $all)
+    };
+    (@munch $ctx:tt $budget:tt $all:tt [] [] [] $orig:tt match $($rest:tt)*) => {
+        $crate::__bail_expr!(@plain $ctx $all)
+    };
+
+    // A top-level `=>` can only be a pattern match.
+    (@munch $ctx:tt $budget:tt [$($all:tt)+] $split:tt $cur:tt [] $orig:tt => $($rest:tt)*) => {
+        $crate::__unwrap_or!($ctx match $($all)+)
+    };
+
+    // Finish with a comparison, if one was found.
+    (@munch $ctx:tt $budget:tt $all:tt [$left:tt $op:tt] [$($right:tt)+] [] $orig:tt) => {
+        $crate::__unwrap_or!($ctx $left $op [$($right)+])
+    };
+    (@munch $ctx:tt $budget:tt $all:tt $split:tt $cur:tt $depth:tt $orig:tt) => {
+        $crate::__bail_expr!(@plain $ctx $all)
+    };
+
+    // Stop classifying once the budget runs out.
+    (@munch $ctx:tt [] [$($all:tt)+] $split:tt $cur:tt $depth:tt $orig:tt $($rest:tt)*) => {
+        $crate::__bail_expr!(@probe $ctx [$($all)+] $($all)+)
+    };
+
+    // Track the depth of turbofish generics and qualified paths.
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt [$($cur:tt)*] [$($depth:tt)*]
+        [$x:tt $y:tt $($orig:tt)*] :: < $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(
+            @munch
+            $ctx
+            [$($budget)*]
+            $all
+            $split
+            [$($cur)* $x $y]
+            [< $($depth)*]
+            [$($rest)*]
+            $($rest)*
+        )
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt [] [] [$x:tt $($orig:tt)*]
+        < $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@munch $ctx [$($budget)*] $all $split [$x] [<] [$($rest)*] $($rest)*)
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt [$($cur:tt)*] [< $($depth:tt)*]
+        [$x:tt $($orig:tt)*] < $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(
+            @munch
+            $ctx
+            [$($budget)*]
+            $all
+            $split
+            [$($cur)* $x]
+            [< < $($depth)*]
+            [$($rest)*]
+            $($rest)*
+        )
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt [$($cur:tt)*] [< $($depth:tt)*]
+        [$x:tt $($orig:tt)*] > $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(
+            @munch $ctx [$($budget)*] $all $split [$($cur)* $x] [$($depth)*] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt [$($cur:tt)*] [< < $($depth:tt)*]
+        [$x:tt $($orig:tt)*] >> $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(
+            @munch $ctx [$($budget)*] $all $split [$($cur)* $x] [$($depth)*] [$($rest)*] $($rest)*
+        )
+    };
+
+    // Split on a top-level comparison operator.
+    (
+        @munch $ctx:tt $budget:tt $all:tt $split:tt $cur:tt [] [$op:tt $($orig:tt)*]
+        == $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@split $ctx $budget $all $split $cur $op $($rest)*)
+    };
+    (
+        @munch $ctx:tt $budget:tt $all:tt $split:tt $cur:tt [] [$op:tt $($orig:tt)*]
+        != $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@split $ctx $budget $all $split $cur $op $($rest)*)
+    };
+    (
+        @munch $ctx:tt $budget:tt $all:tt $split:tt $cur:tt [] [$op:tt $($orig:tt)*]
+        < $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@split $ctx $budget $all $split $cur $op $($rest)*)
+    };
+    (
+        @munch $ctx:tt $budget:tt $all:tt $split:tt $cur:tt [] [$op:tt $($orig:tt)*]
+        <= $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@split $ctx $budget $all $split $cur $op $($rest)*)
+    };
+    (
+        @munch $ctx:tt $budget:tt $all:tt $split:tt $cur:tt [] [$op:tt $($orig:tt)*]
+        > $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@split $ctx $budget $all $split $cur $op $($rest)*)
+    };
+    (
+        @munch $ctx:tt $budget:tt $all:tt $split:tt $cur:tt [] [$op:tt $($orig:tt)*]
+        >= $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@split $ctx $budget $all $split $cur $op $($rest)*)
+    };
+
+    // Operators with lower precedence than comparisons rule out a single comparison.
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt $cur:tt [] $orig:tt
+        && $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@munch $ctx [$($budget)*] $all [!] [] [] [$($rest)*] $($rest)*)
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt $cur:tt [] $orig:tt
+        || $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@munch $ctx [$($budget)*] $all [!] [] [] [$($rest)*] $($rest)*)
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt $cur:tt [] $orig:tt
+        .. $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@munch $ctx [$($budget)*] $all [!] [] [] [$($rest)*] $($rest)*)
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt $cur:tt [] $orig:tt
+        ..= $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@munch $ctx [$($budget)*] $all [!] [] [] [$($rest)*] $($rest)*)
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt $cur:tt [] $orig:tt
+        = $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(@munch $ctx [$($budget)*] $all [!] [] [] [$($rest)*] $($rest)*)
+    };
+
+    // Add a method call or field access to the current operand in one step.
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt [$($cur:tt)*] []
+        [. $m:ident ($($args:tt)*) $($orig:tt)*] $t1:tt $t2:tt $t3:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(
+            @munch $ctx [$($budget)*] $all $split [$($cur)* $t1 $t2 $t3] [] [$($rest)*] $($rest)*
+        )
+    };
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt [$($cur:tt)*] []
+        [. $f:ident $($orig:tt)*] $t1:tt $t2:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(
+            @munch $ctx [$($budget)*] $all $split [$($cur)* $t1 $t2] [] [$($rest)*] $($rest)*
+        )
+    };
+
+    // Add a token to the current operand.
+    (
+        @munch $ctx:tt [$b:tt $($budget:tt)*] $all:tt $split:tt [$($cur:tt)*] $depth:tt $orig:tt
+        $tt:tt $($rest:tt)*
+    ) => {
+        $crate::__bail_expr!(
+            @munch $ctx [$($budget)*] $all $split [$($cur)* $tt] $depth [$($rest)*] $($rest)*
+        )
+    };
+
+    // Start the right operand, unless the comparison is missing a left operand or is chained.
+    (@split $ctx:tt $budget:tt $all:tt [] [$($left:tt)+] $op:tt $($rest:tt)*) => {
+        $crate::__bail_expr!(@munch $ctx $budget $all [[$($left)+] $op] [] [] [$($rest)*] $($rest)*)
+    };
+    (@split $ctx:tt $budget:tt $all:tt $split:tt $cur:tt $op:tt $($rest:tt)*) => {
+        $crate::__bail_expr!(@munch $ctx $budget $all [!] [] [] [$($rest)*] $($rest)*)
+    };
+
+    // Unwrap the expression as a pattern match if it has a top-level `=>`, or as-is otherwise.
+    (@probe $ctx:tt [$($all:tt)+] $e:expr => $($rest:tt)*) => {
+        $crate::__unwrap_or!($ctx match $($all)+)
+    };
+    (@probe $ctx:tt $all:tt $($rest:tt)*) => {
+        $crate::__bail_expr!(@plain $ctx $all)
+    };
+
+    // Unwrap the expression as-is.
+    (@plain $ctx:tt [$($expr:tt)+]) => {
+        $crate::__unwrap_or!($ctx, $($expr)+, $($expr)+)
+    };
+
+    // Start classifying the expression.
+    ($ctx:tt $($expr:tt)+) => {
+        $crate::__bail_expr!(
+            @munch
+            $ctx
+            [_ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _]
+            [$($expr)+]
+            []
+            []
+            []
+            [$($expr)+]
+            $($expr)+
+        )
+    };
+}
+
+/// A helper macro to unwrap on success, or log the failure and do something else or fall back to
+/// a value.
+///
+/// The failure is logged according to the log mode, and captured variables are only evaluated on
+/// failure.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __unwrap_or {
+    // Retry comparisons as plain expressions, so that the operands are re-evaluated.
+    (
+        (
+            $mode:ident
+            $else:tt
+            [quiet_if $quiet_if:tt attempts [$attempts:tt] $($opt:tt)*]
+            $capture:tt
+        )
+        [$($left:tt)+] $op:tt [$($right:tt)+]
+    ) => {
+        $crate::__unwrap_or!(
+            ($mode $else [quiet_if $quiet_if attempts [$attempts] $($opt)*] $capture),
+            $($left)+ $op $($right)+,
+            $($left)+ $op $($right)+
+        )
+    };
+
+    // Reject retrying patterns.
+    (
+        (
+            $mode:ident
+            $else:tt
+            [quiet_if $quiet_if:tt attempts [$attempts:tt] $($opt:tt)*]
+            $capture:tt
+        )
+        let $($rest:tt)*
+    ) => {
+        ::core::compile_error!("the `attempts` bail option isn't supported with patterns")
+    };
+    (
+        (
+            $mode:ident
+            $else:tt
+            [quiet_if $quiet_if:tt attempts [$attempts:tt] $($opt:tt)*]
+            $capture:tt
+        )
+        match $($rest:tt)*
+    ) => {
+        ::core::compile_error!("the `attempts` bail option isn't supported with patterns")
+    };
+
+    // Compare the operands by reference, and capture them on failure.
+    //
+    // Operands that don't implement `Debug` are captured as a placeholder.
+    (($mode:ident $else:tt $opt:tt [$($capture:tt)*]) [$($left:tt)+] $op:tt [$($right:tt)+]) => {
+        match (&($($left)+), &($($right)+)) {
+            (__left, __right) => $crate::__unwrap_or!(
+                (
+                    $mode
+                    $else
+                    $opt
+                    [
+                        left = $crate::__debug_select!(__left),
+                        right = $crate::__debug_select!(__right),
+                        $($capture)*
+                    ]
+                ),
+                $($left)+ $op $($right)+,
+                *__left $op *__right
+            ),
+        }
+    };
+
+    // Match a pattern, binding it for the rest of the block.
+    (($mode:ident $else:tt $opt:tt $capture:tt) let $pat:pat = $expr:expr) => {
+        let __value = $expr;
+        let $pat = __value else {
+            $crate::__bail_log!($mode, $expr, __value, $opt, $capture);
+            $crate::__bail_else!($else, $expr, $opt, __value);
+        };
+    };
+
+    // Match a pattern, evaluating to the given value.
+    (
+        ($mode:ident $else:tt $opt:tt $capture:tt)
+        match $pat:pat = $expr:expr => $value:expr
+    ) => {
+        match $expr {
+            $pat => $value,
+            __value => {
+                $crate::__bail_log!($mode, $expr, __value, $opt, $capture);
+                $crate::__bail_else!($else, $expr, $opt, __value)
+            }
+        }
+    };
+
+    // Unwrap the value, retrying and logging each failed attempt.
+    (
+        (
+            $mode:ident
+            $else:tt
+            [
+                quiet_if $quiet_if:tt
+                attempts [($($attempts:tt)+)]
+                backoff [$(($($backoff:tt)+))?]
+                level $level:tt
+                msg $msg:tt
+                target $target:tt
+            ]
+            [$($capture:tt)*]
+        ),
+        $expr:expr,
+        $value:expr
+    ) => {
+        match {
+            let mut __attempt: u32 = 0;
+            loop {
+                __attempt += 1;
+                match $crate::IntoResult::into_result($value) {
+                    ::core::result::Result::Ok(x) => break ::core::result::Result::Ok(x),
+                    ::core::result::Result::Err(__err) => {
+                        $crate::__bail_log!(
+                            $mode,
+                            $expr,
+                            __err,
+                            [
+                                quiet_if $quiet_if
+                                attempts []
+                                backoff []
+                                level $level
+                                msg $msg
+                                target $target
+                            ],
+                            [attempt = __attempt, $($capture)*]
+                        );
+                        if __attempt >= ($($attempts)+) {
+                            break ::core::result::Result::Err(__err);
+                        }
+                        $(::std::thread::sleep($crate::__backoff($($backoff)+, __attempt));)?
+                    }
+                }
+            }
+        } {
+            ::core::result::Result::Ok(x) => x,
+            ::core::result::Result::Err(__err) => $crate::__bail_else!(
+                $else,
+                $expr,
+                [
+                    quiet_if $quiet_if
+                    attempts [($($attempts)+)]
+                    backoff [$(($($backoff)+))?]
+                    level $level
+                    msg $msg
+                    target $target
+                ],
+                __err
+            ),
+        }
+    };
+
+    // Unwrap the value, logging the failure as the expression.
+    (($mode:ident $else:tt $opt:tt $capture:tt), $expr:expr, $value:expr) => {
+        match $crate::IntoResult::into_result($value) {
+            ::core::result::Result::Ok(x) => x,
+            ::core::result::Result::Err(__err) => {
+                $crate::__bail_log!($mode, $expr, __err, $opt, $capture);
+                $crate::__bail_else!($else, $expr, $opt, __err)
+            }
+        }
+    };
+}
+
+/// A helper macro to do something else after a failure, optionally binding the failure value and
+/// the bail site.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bail_else {
+    (
+        { |$err:ident, $site:ident| $($else:tt)* },
+        $expr:expr,
+        [
+            quiet_if $quiet_if:tt
+            attempts $attempts:tt
+            backoff $backoff:tt
+            level $level:tt
+            msg [$(($($msg:tt)+))?]
+            target $target:tt
+        ],
+        $value:ident
+    ) => {{
+        let $err = $value;
+        let $site = $crate::__bail_site!($expr $(, $($msg)+)?);
+        $($else)*
+    }};
+    ({ |$err:ident| $($else:tt)* }, $expr:expr, $opt:tt, $value:ident) => {{
+        let $err = $value;
+        $($else)*
+    }};
+    ({ $($else:tt)* }, $expr:expr, $opt:tt, $value:ident) => {{
+        $($else)*
+    }};
+}
+
+/// A helper macro to log the failure according to the log mode.
+///
+/// In every mode except `quiet`, the failure is logged at the level chosen by its [`BailPolicy`],
+/// if it implements it, and the `quiet_if` option is respected. A failure that bails quietly
+/// doesn't use up the `once` mode's single log, or count as a hit in the `escalate` mode.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bail_log {
+    (
+        log,
+        $expr:expr,
+        $err:ident,
+        [
+            quiet_if [$(($($quiet_if:tt)+))?]
+            attempts $attempts:tt
+            backoff $backoff:tt
+            level $level:tt
+            msg [$(($($msg:tt)+))?]
+            target [$(($($target:tt)+))?]
+        ],
+        [$($name:ident = $value:expr),* $(,)?]
+    ) => {
+        if !(false $(|| $crate::__quiet_if(&$err, $($quiet_if)+))?) {
+            $crate::__log_bail_at!(
+                [$($($target)+)?],
+                [$($($msg)+)?],
+                $crate::__bail_log!(@level $level $err),
+                $expr,
+                $err,
+                $($name = $value,)*
+            );
+        }
+    };
+
+    (quiet, $expr:expr, $err:ident, $opt:tt, $capture:tt) => {};
+
+    (
+        once,
+        $expr:expr,
+        $err:ident,
+        [
+            quiet_if [$(($($quiet_if:tt)+))?]
+            attempts $attempts:tt
+            backoff $backoff:tt
+            level $level:tt
+            msg [$(($($msg:tt)+))?]
+            target [$(($($target:tt)+))?]
+        ],
+        [$($name:ident = $value:expr),* $(,)?]
+    ) => {
+        static __SHOULD_LOG: $crate::__atomic::AtomicBool = $crate::__atomic::AtomicBool::new(true);
+        if !(false $(|| $crate::__quiet_if(&$err, $($quiet_if)+))?) {
+            let __level = $crate::__bail_log!(@level $level $err);
+            if __level.__is_static_enabled()
+                && __SHOULD_LOG.swap(false, $crate::__atomic::Ordering::Relaxed)
+            {
+                $crate::__log_bail_at!(
+                    [$($($target)+)?],
+                    [$($($msg)+)?],
+                    __level,
+                    $expr,
+                    $err,
+                    $($name = $value,)*
+                );
+            }
+        }
+    };
+
+    (
+        escalate,
+        $expr:expr,
+        $err:ident,
+        [
+            quiet_if [$(($($quiet_if:tt)+))?]
+            attempts $attempts:tt
+            backoff $backoff:tt
+            level $level:tt
+            msg [$(($($msg:tt)+))?]
+            target [$(($($target:tt)+))?]
+        ],
+        [$($name:ident = $value:expr),* $(,)?]
+    ) => {
+        static __HITS: $crate::__atomic::AtomicU32 = $crate::__atomic::AtomicU32::new(0);
+        if !(false $(|| $crate::__quiet_if(&$err, $($quiet_if)+))?) {
+            $crate::__log_bail_at!(
+                [$($($target)+)?],
+                [$($($msg)+)?],
+                $crate::__escalate(&__HITS, $crate::__bail_log!(@level $level $err)),
+                $expr,
+                $err,
+                $($name = $value,)*
+            );
+        }
+    };
+
+    // Use the `level` option if set, or the level chosen by the failure value's policy otherwise.
+    (@level [] $err:ident) => {{
+        #[allow(unused_imports)]
+        use $crate::__policy::{ViaDefault as _, ViaPolicy as _};
+        (&$crate::__policy::Wrap(&$err)).__bail_level()
+    }};
+    (@level [($($level:tt)+)] $err:ident) => {
+        $crate::__fixed_level($($level)+)
+    };
+}
+
+/// Call a `quiet_if` predicate on the failure value.
+#[doc(hidden)]
+pub fn __quiet_if<E>(err: &E, quiet_if: impl FnOnce(&E) -> bool) -> bool {
+    quiet_if(err)
+}
+
+/// Return the delay before the next attempt, doubling the backoff after each failed attempt.
+#[doc(hidden)]
+pub fn __backoff(backoff: core::time::Duration, attempt: u32) -> core::time::Duration {
+    backoff.saturating_mul(1 << attempt.saturating_sub(1).min(31))
+}
+
+/// Unwrap on success, or bail as configured by keyword options.
+///
+/// The other bail macros are shorthands for combinations of these options. For example,
+/// [`or_continue_log_once!`] is `bail!(expr, on_fail = continue, log = once)`.
+///
+/// Accepts the following options after the expression, in any order:
+/// - `on_fail = return`, `continue`, `break`, `default`, `exit`, or `err`, followed by an optional
+///   value as accepted by the initial argument of [`or_return!`], [`or_continue!`], [`or_break!`],
+///   [`or_default!`], [`or_exit!`], or [`or_return_err!`]. Defaults to `return`.
+/// - `log = always`, `quiet`, `once`, or `escalate`. Defaults to `always`.
+/// - `level = level`, to log at a fixed [`Level`] instead of the current log level. Accepts
+///   `error`, `warn`, `info`, `debug`, `trace`, and `off` as a shorthand.
+/// - `msg = "message"`, to prefix the bail message with a `&'static str` message. The message is
+///   also recorded in the [`BailSite`] of [`Bailed`] errors and fallback sinks.
+/// - `target = "target"`, to log with a custom target. This is only used by the `log` and `tracing`
+///   backends, and must be a constant with `tracing`. The fallback backend ignores it, and the
+///   `defmt` backend, which has no targets, rejects it with a compile error.
+/// - `quiet_if = predicate`, `attempts = n`, and `backoff = duration`, as in the other macros.
+///
+/// Accepts an optional list of variables after a `;` at the end, to include in the bail message.
+/// The list can end with an `else { ... }` block to run on failure, before bailing.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// fn total(players: &[u32], scores: &HashMap<u32, i32>) -> i32 {
+///     let mut total = 0;
+///     'outer: for id in players {
+///         // Log the first missing score at the `error` level, and skip the player.
+///         total += tiny_bail::bail!(
+///             scores.get(id),
+///             on_fail = continue 'outer,
+///             log = once,
+///             level = error,
+///             msg = "missing score",
+///         );
+///     }
+///     total
+/// }
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([bail [] []] $($args)*)
+    };
+}
+
+/// Import bail macros under aliases of your choice.
+///
+/// Expands to a `use` item for each `alias = macro_name` pair, with an optional visibility. The
+/// macro name can be a full name like `or_return`, or a tiny alias like `r`. This is an alternative
+/// to glob importing [`prelude`], for when its tiny aliases would collide with other macros.
+///
+/// # Example
+///
+/// ```rust
+/// mod bail {
+///     // Make the aliases available to the rest of the crate.
+///     tiny_bail::aliases!(pub(crate) ret = r, cont = or_continue);
+/// }
+///
+/// use bail::{cont, ret};
+///
+/// fn sum(values: &[Option<i32>]) -> i32 {
+///     ret!(!values.is_empty());
+///     let mut sum = 0;
+///     for &value in values {
+///         sum += cont!(value);
+///     }
+///     sum
+/// }
+/// ```
+#[macro_export]
+macro_rules! aliases {
+    ($vis:vis $($alias:ident = $name:ident),* $(,)?) => {
+        $(
+            #[allow(unused_imports)]
+            $vis use $crate::__aliases::$name as $alias;
+        )*
+    };
+}
+
+/// Define crate-local bail macros with preset default options.
+///
+/// Generates macros that unwrap on success, or log the failure and return, continue, or break. They
+/// accept the same arguments and options as [`bail!`], except for `on_fail`. Options set at the
+/// call site take precedence over the defaults.
+///
+/// Accepts the following keys, in any order:
+/// - `level`, `target`, and `msg`, to set the default of the corresponding [`bail!`] option. The
+///   `msg` default prefixes every bail message, unless the call site sets its own `msg`.
+/// - `strict: true`, to panic with the bail message on failure instead of returning, continuing,
+///   or breaking. The failure is still logged first. Defaults to `false`.
+/// - `aliases: (ret, cont, brk)`, to name the return, continue, and break macros. Defaults to
+///   `(or_return, or_continue, or_break)`.
+///
+/// Only the return, continue, and break macros are generated. Use the crate's own macros for the
+/// other bail actions and log modes, such as [`or_default!`] or [`or_return_log_once!`].
+///
+/// The macros are scoped like any `macro_rules!` macro, so they're available in modules declared
+/// after them. They're also re-exported with `pub(crate) use`, so they can be imported by path
+/// elsewhere, as in `use crate::r;`.
+///
+/// # Example
+///
+/// ```rust
+/// tiny_bail::define_bail_macros! {
+///     level: error,
+///     msg: "network failure",
+///     aliases: (r, c, b),
+/// }
+///
+/// fn send(packets: &[Option<u8>]) -> u32 {
+///     let mut sent = 0;
+///     for &packet in packets {
+///         // Log at the `error` level, prefixed with "network failure".
+///         let packet = c!(packet);
+///         // Log at the `warn` level instead.
+///         sent += r!(packet.checked_add(1), level = warn) as u32;
+///     }
+///     sent
+/// }
+/// ```
+#[macro_export]
+macro_rules! define_bail_macros {
+    ($($args:tt)*) => {
+        $crate::__define_bail_macros!(
+            @munch ($) [] [or_return or_continue or_break] [return continue break] $($args)*
+        );
+    };
+}
+
+/// A helper macro to parse the keys of [`define_bail_macros!`] and generate the macros.
+///
+/// The `$` token is passed in as the first argument, so that it can be used in the generated
+/// macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __define_bail_macros {
+    // Parse the next key, or finish.
+    (
+        @munch $d:tt $opt:tt $names:tt $kinds:tt
+        aliases: ($ret:ident, $cont:ident, $brk:ident $(,)?) $(, $($rest:tt)*)?
+    ) => {
+        $crate::__define_bail_macros!(@munch $d $opt [$ret $cont $brk] $kinds $($($rest)*)?);
+    };
+    (@munch $d:tt $opt:tt $names:tt $kinds:tt strict: true $(, $($rest:tt)*)?) => {
+        $crate::__define_bail_macros!(@munch $d $opt $names [panic panic panic] $($($rest)*)?);
+    };
+    (@munch $d:tt $opt:tt $names:tt $kinds:tt strict: false $(, $($rest:tt)*)?) => {
+        $crate::__define_bail_macros!(
+            @munch $d $opt $names [return continue break] $($($rest)*)?
+        );
+    };
+    (@munch $d:tt $opt:tt $names:tt $kinds:tt strict: $($rest:tt)*) => {
+        ::core::compile_error!("expected `strict: true` or `strict: false`");
+    };
+    (@munch $d:tt $opt:tt $names:tt $kinds:tt $key:ident: $($rest:tt)*) => {
+        $crate::__define_bail_macros!(@value $d $opt $names $kinds $key [] $($rest)*);
+    };
+    (
+        @munch ($d:tt) [$($opt:tt)*] [$ret:ident $cont:ident $brk:ident]
+        [$ret_kind:tt $cont_kind:tt $brk_kind:tt]
+    ) => {
+        #[allow(unused_macros)]
+        macro_rules! $ret {
+            ($d($d args:tt)*) => {
+                $crate::__bail_parse!(@opt [$($opt)*] [bail [$ret_kind] []] $d($d args)*)
+            };
+        }
+        #[allow(unused_imports)]
+        pub(crate) use $ret;
+
+        #[allow(unused_macros)]
+        macro_rules! $cont {
+            ($d($d args:tt)*) => {
+                $crate::__bail_parse!(@opt [$($opt)*] [bail [$cont_kind] []] $d($d args)*)
+            };
+        }
+        #[allow(unused_imports)]
+        pub(crate) use $cont;
+
+        #[allow(unused_macros)]
+        macro_rules! $brk {
+            ($d($d args:tt)*) => {
+                $crate::__bail_parse!(@opt [$($opt)*] [bail [$brk_kind] []] $d($d args)*)
+            };
+        }
+        #[allow(unused_imports)]
+        pub(crate) use $brk;
+    };
+    (@munch $d:tt $opt:tt $names:tt $kinds:tt $($rest:tt)+) => {
+        ::core::compile_error!(
+            "expected `key: value`, with a key of `level`, `target`, `msg`, `strict`, or `aliases`"
+        );
+    };
+
+    // Split off the value of the current key on a comma, or at the end.
+    (@value $d:tt $opt:tt $names:tt $kinds:tt $key:ident [$($value:tt)*] , $($rest:tt)*) => {
+        $crate::__define_bail_macros!(@push $d $opt $names $kinds $key [$($value)*] $($rest)*);
+    };
+    (@value $d:tt $opt:tt $names:tt $kinds:tt $key:ident [$($value:tt)*]) => {
+        $crate::__define_bail_macros!(@push $d $opt $names $kinds $key [$($value)*]);
+    };
+    (
+        @value $d:tt $opt:tt $names:tt $kinds:tt $key:ident [$($value:tt)*]
+        $tt:tt $($rest:tt)*
+    ) => {
+        $crate::__define_bail_macros!(
+            @value $d $opt $names $kinds $key [$($value)* $tt] $($rest)*
+        );
+    };
+
+    // Add the value as a default option.
+    (@push $d:tt [$($opt:tt)*] $names:tt $kinds:tt level [$($value:tt)+] $($rest:tt)*) => {
+        $crate::__define_bail_macros!(
+            @munch $d [$($opt)* @default level ($($value)+)] $names $kinds $($rest)*
+        );
+    };
+    (@push $d:tt [$($opt:tt)*] $names:tt $kinds:tt target [$($value:tt)+] $($rest:tt)*) => {
+        $crate::__define_bail_macros!(
+            @munch $d [$($opt)* @default target ($($value)+)] $names $kinds $($rest)*
+        );
+    };
+    (@push $d:tt [$($opt:tt)*] $names:tt $kinds:tt msg [$($value:tt)+] $($rest:tt)*) => {
+        $crate::__define_bail_macros!(
+            @munch $d [$($opt)* @default msg ($($value)+)] $names $kinds $($rest)*
+        );
+    };
+    (@push $d:tt $opt:tt $names:tt $kinds:tt $key:ident $value:tt $($rest:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "unsupported key: `",
+            ::core::stringify!($key),
+            "`",
+        ));
+    };
+}
+
+/// Unwrap on success, or log the failure and return.
+///
+/// Returns [`Default::default()`] unless an initial argument is provided to return instead, or a
+/// closure to compute it from the failure value.
+///
+/// Accepts an optional `quiet_if = predicate` after the expression, to bail quietly when the
+/// predicate returns true for the failure value.
+///
+/// Accepts an optional list of variables after a `;` at the end, to include in the bail message.
+/// The list can end with an `else { ... }` block to run on failure, before bailing.
+///
+/// A comparison like `hp > 0` includes both operands in the bail message as captured variables,
+/// as in `` `hp > 0` is `false`, left = `-3`, right = `0` ``. The comparison operator must be within the first 24 token trees of the expression, where a
+/// parenthesized group counts as one. Past that, the operands are left out of the bail message,
+/// unless the expression is shortened, as in `(a + b + c) > 0`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::{
+///     collections::HashMap,
+///     sync::mpsc::{Receiver, TryRecvError},
+/// };
+/// use tiny_bail::prelude::*;
+///
+/// fn poll(rx: &Receiver<i32>) -> i32 {
+///     // Bail on every failure, but only log if the channel is disconnected.
+///     r!(rx.try_recv(), quiet_if = |e| matches!(e, TryRecvError::Empty))
+/// }
+///
+/// fn lookup(map: &HashMap<u32, i32>, key: u32) -> i32 {
+///     // Include the value of `key` in the bail message.
+///     *r!(map.get(&key); key)
+/// }
+///
+/// fn parse(s: &str) -> String {
+///     // Return a message computed from the error.
+///     let n: i32 = r!(|e| format!("invalid number: {e}"), s.parse());
+///     format!("parsed {n}")
+/// }
+/// ```
+#[macro_export]
+macro_rules! or_return {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr return log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the failure and continue.
+///
+/// Accepts an optional 'label as the first argument.
+///
+/// Accepts an optional `quiet_if = predicate` after the expression, to bail quietly when the
+/// predicate returns true for the failure value.
+///
+/// Accepts an optional list of variables after a `;` at the end, to include in the bail message.
+/// The list can end with an `else { ... }` block to run on failure, before bailing.
+#[macro_export]
+macro_rules! or_continue {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr continue log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the failure and break.
+///
+/// Accepts an optional 'label as the first argument, or `'label => value` to break with a value.
+///
+/// Accepts an optional `quiet_if = predicate` after the expression, to bail quietly when the
+/// predicate returns true for the failure value.
+///
+/// Accepts an optional list of variables after a `;` at the end, to include in the bail message.
+/// The list can end with an `else { ... }` block to run on failure, before bailing.
+#[macro_export]
+macro_rules! or_break {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr break log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or quietly discard the failure and return.
+///
+/// Returns [`Default::default()`] unless an initial argument is provided to return instead, or a
+/// closure to compute it from the failure value.
+#[macro_export]
+macro_rules! or_return_quiet {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr return quiet] $($args)*)
+    };
+}
+
+/// Unwrap on success, or quietly discard the failure and continue.
+///
+/// Accepts an optional 'label as the first argument.
+#[macro_export]
+macro_rules! or_continue_quiet {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr continue quiet] $($args)*)
+    };
+}
+
+/// Unwrap on success, or quietly discard the failure and break.
+///
+/// Accepts an optional 'label as the first argument, or `'label => value` to break with a value.
+#[macro_export]
+macro_rules! or_break_quiet {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr break quiet] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the first failure and return.
+///
+/// Returns [`Default::default()`] unless an initial argument is provided to return instead, or a
+/// closure to compute it from the failure value.
+#[macro_export]
+macro_rules! or_return_log_once {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr return once] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the first failure and continue.
+///
+/// Accepts an optional 'label as the first argument.
+#[macro_export]
+macro_rules! or_continue_log_once {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr continue once] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the first failure and break.
+///
+/// Accepts an optional 'label as the first argument, or `'label => value` to break with a value.
+#[macro_export]
+macro_rules! or_break_log_once {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr break once] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the failure at an escalating level and return.
+///
+/// Returns [`Default::default()`] unless an initial argument is provided to return instead, or a
+/// closure to compute it from the failure value.
+///
+/// The log level escalates as this site keeps failing. The hit thresholds are process-wide and
+/// shared by every escalating bail site. See [`set_escalation`].
+#[macro_export]
+macro_rules! or_return_escalate {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr return escalate] $($args)*)
+    };
+}
+
+/// Unwrap several expressions into a tuple on success, or log the first failure and return.
+///
+/// Returns [`Default::default()`].
+///
+/// The index of the failed expression is included in the bail message, along with the expression
+/// itself.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use tiny_bail::prelude::ret::*;
+///
+/// fn total(map: &HashMap<&str, i32>) -> i32 {
+///     let (a, b, c) = r_all!(map.get("a"), map.get("b"), map.get("c"));
+///     a + b + c
+/// }
+/// ```
+#[macro_export]
+macro_rules! or_return_all {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([all return log] $($args)*)
+    };
+}
+
+/// Unwrap each `?`-terminated step of a method chain on success, or log the first failed step and
+/// return.
+///
+/// Returns [`Default::default()`] unless an initial argument is provided to return instead, or a
+/// closure to compute it from the failure value.
+///
+/// The failed step is logged as the chain up to and including that step. Any steps after the last
+/// `?` are applied to the unwrapped value as-is.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use tiny_bail::prelude::ret::*;
+///
+/// fn first_len(map: &HashMap<u32, Vec<String>>, key: u32) -> usize {
+///     // Logs `map.get(&key)` or `map.get(&key)?.first()`, whichever fails.
+///     r_chain!(map.get(&key)?.first()?.len())
+/// }
+/// ```
+#[macro_export]
+macro_rules! or_return_chain {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([chain return log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the failure and return it as an error.
+///
+/// Returns `Err(From::from(failure))`, like `?` with a bail message. Accepts an optional closure
+/// as the first argument, to wrap the failure value with context before converting it.
+///
+/// Accepts an optional `quiet_if = predicate` after the expression, to bail quietly when the
+/// predicate returns true for the failure value.
+///
+/// Accepts an optional list of variables after a `;` at the end, to include in the bail message.
+/// The list can end with an `else { ... }` block to run on failure, before bailing.
+///
+/// # Example
+///
+/// ```rust
+/// use std::{fs, io};
+/// use tiny_bail::prelude::ret::*;
+///
+/// #[derive(Debug)]
+/// enum ConfigError {
+///     Io(io::Error),
+///     Read(String, io::Error),
+/// }
+///
+/// impl From<io::Error> for ConfigError {
+///     fn from(e: io::Error) -> Self {
+///         Self::Io(e)
+///     }
+/// }
+///
+/// fn load(path: &str) -> Result<String, ConfigError> {
+///     let config = r_err!(fs::read_to_string(path));
+///     // Wrap the error with the path.
+///     let extra = r_err!(|e| ConfigError::Read(path.into(), e), fs::read_to_string("extra"));
+///     Ok(config + &extra)
+/// }
+/// ```
+#[macro_export]
+macro_rules! or_return_err {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr err log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the failure and return it as a [`Bailed`] error.
+///
+/// Returns `Err(From::from(bailed))`, where `bailed` records the bail site and the failure value as
+/// its source, if possible.
+///
+/// Accepts an optional `quiet_if = predicate` after the expression, to bail quietly when the
+/// predicate returns true for the failure value.
+///
+/// Accepts an optional list of variables after a `;` at the end, to include in the bail message.
+/// The list can end with an `else { ... }` block to run on failure, before bailing.
+#[macro_export]
+macro_rules! or_return_bailed {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr bailed log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or quietly return the failure as a [`Bailed`] error.
+///
+/// Returns `Err(From::from(bailed))`, where `bailed` records the bail site and the failure value as
+/// its source, if possible. This lets libraries leave it to the caller to decide whether to log.
+#[macro_export]
+macro_rules! or_return_bailed_quiet {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr bailed quiet] $($args)*)
+    };
+}
+
+/// Unwrap on success, or retry up to the given number of attempts, logging each failed attempt,
+/// and return.
+///
+/// Returns [`Default::default()`] unless a second argument is provided to return instead, or a
+/// closure to compute it from the failure value.
+///
+/// Accepts an optional `backoff = duration` after the expression, to sleep between attempts,
+/// doubling the duration after each failed attempt. This requires `std`.
+///
+/// To retry and then continue or break instead, pass `attempts = n` to any other bail macro.
+///
+/// # Example
+///
+/// ```rust
+/// use std::{fs::File, time::Duration};
+/// use tiny_bail::prelude::ret::*;
+///
+/// fn open(path: &str) -> Option<File> {
+///     // Try to open the file 3 times, waiting 10ms and then 20ms between attempts.
+///     let file = rr!(3, File::open(path), backoff = Duration::from_millis(10));
+///     Some(file)
+/// }
+/// ```
+#[macro_export]
+macro_rules! or_retry {
+    ($attempts:expr, $($args:tt)*) => {
+        $crate::__bail_parse!(@opt [attempts ($attempts)] [expr return log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or print the failure to stderr and exit the process.
+///
+/// Exits with code 1 unless an initial argument is provided to exit with instead. The code location
+/// is included in the message in debug builds only, unless set otherwise with
+/// [`set_exit_location`]. This requires `std`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use tiny_bail::prelude::exit::*;
+///
+/// fn main() {
+///     // Print "error: `std::env::args().nth(1)` is `None`" and exit with code 2.
+///     let path = ex!(2, std::env::args().nth(1));
+/// }
+/// ```
+#[macro_export]
+macro_rules! or_exit {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr exit quiet] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the failure at an escalating level and continue.
+///
+/// Accepts an optional 'label as the first argument.
+///
+/// The log level escalates as this site keeps failing. The hit thresholds are process-wide and
+/// shared by every escalating bail site. See [`set_escalation`].
+#[macro_export]
+macro_rules! or_continue_escalate {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr continue escalate] $($args)*)
+    };
+}
+
+/// Unwrap several expressions into a tuple on success, or log the first failure and continue.
+///
+/// Accepts an optional 'label as the first argument.
+///
+/// The index of the failed expression is included in the bail message, along with the expression
+/// itself.
+#[macro_export]
+macro_rules! or_continue_all {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([all continue log] $($args)*)
+    };
+}
+
+/// Unwrap each `?`-terminated step of a method chain on success, or log the first failed step and
+/// continue.
+///
+/// Accepts an optional 'label as the first argument.
+///
+/// The failed step is logged as the chain up to and including that step. Any steps after the last
+/// `?` are applied to the unwrapped value as-is.
+#[macro_export]
+macro_rules! or_continue_chain {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([chain continue log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the failure at an escalating level and break.
+///
+/// Accepts an optional 'label as the first argument, or `'label => value` to break with a value.
+///
+/// The log level escalates as this site keeps failing. The hit thresholds are process-wide and
+/// shared by every escalating bail site. See [`set_escalation`].
+#[macro_export]
+macro_rules! or_break_escalate {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr break escalate] $($args)*)
+    };
+}
+
+/// Unwrap several expressions into a tuple on success, or log the first failure and break.
+///
+/// Accepts an optional 'label as the first argument, or `'label => value` to break with a value.
+///
+/// The index of the failed expression is included in the bail message, along with the expression
+/// itself.
+#[macro_export]
+macro_rules! or_break_all {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([all break log] $($args)*)
+    };
+}
+
+/// Unwrap each `?`-terminated step of a method chain on success, or log the first failed step and
+/// break.
+///
+/// Accepts an optional 'label as the first argument, or `'label => value` to break with a value.
+///
+/// The failed step is logged as the chain up to and including that step. Any steps after the last
+/// `?` are applied to the unwrapped value as-is.
+#[macro_export]
+macro_rules! or_break_chain {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([chain break log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the failure and fall back to a default value.
+///
+/// Falls back to [`Default::default()`] unless an initial argument is provided to fall back to
+/// instead, or a closure to compute it from the failure value.
+///
+/// Accepts an optional `quiet_if = predicate` after the expression, to bail quietly when the
+/// predicate returns true for the failure value.
+///
+/// Accepts an optional list of variables after a `;` at the end, to include in the bail message.
+/// The list can end with an `else { ... }` block to run on failure, before bailing.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use tiny_bail::prelude::{or::*, *};
+///
+/// fn speed(config: &HashMap<&str, f32>) -> f32 {
+///     // Fall back to 1.0 without leaving the function.
+///     let speed = or!(1.0, config.get("speed").copied());
+///     // Fall back to 0.0, and include the config in the bail message.
+///     let accel = or!(config.get("accel").copied(); config);
+///     speed * accel
+/// }
+/// ```
+#[macro_export]
+macro_rules! or_default {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr or log] $($args)*)
+    };
+}
+
+/// Unwrap on success, or quietly discard the failure and fall back to a default value.
+///
+/// Falls back to [`Default::default()`] unless an initial argument is provided to fall back to
+/// instead, or a closure to compute it from the failure value.
+#[macro_export]
+macro_rules! or_default_quiet {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr or quiet] $($args)*)
+    };
+}
+
+/// Unwrap on success, or log the first failure and fall back to a default value.
+///
+/// Falls back to [`Default::default()`] unless an initial argument is provided to fall back to
+/// instead, or a closure to compute it from the failure value.
+#[macro_export]
+macro_rules! or_default_log_once {
+    ($($args:tt)*) => {
+        $crate::__bail_parse!([expr or once] $($args)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fmt::Debug, sync::Mutex};
+
+    use super::{Bailed, IntoResult};
+
+    // Tests that depend on global state, like the log level, must not run concurrently.
+    pub(crate) static LOCK: Mutex<()> = Mutex::new(());
+
+    // A no-op `defmt` logger, so the tests can link.
+    #[cfg(feature = "defmt")]
+    mod defmt_logger {
+        #[defmt::global_logger]
+        struct Logger;
+
+        unsafe impl defmt::Logger for Logger {
+            fn acquire() {}
+            unsafe fn flush() {}
+            unsafe fn release() {}
+            unsafe fn write(_bytes: &[u8]) {}
+        }
+
+        defmt::timestamp!("");
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    // The explicit borrows are needed for autoref specialization.
+    #[allow(clippy::needless_borrow)]
+    fn defmt_specialization() {
+        #[allow(unused_imports)]
+        use super::__defmt::{Opaque, ViaFormat as _, ViaOpaque as _, Wrap};
+
+        // Failure values that implement `defmt::Format` should be selected as-is.
+        let _: &Option<()> = (&Wrap(&None::<()>)).__defmt_err();
+        let _: &bool = (&Wrap(&false)).__defmt_err();
+
+        // Other failure values should be replaced with a placeholder.
+        struct NotFormat;
+        let _: Opaque = (&Wrap(&NotFormat)).__defmt_err();
+    }
+
+    #[cfg(not(feature = "defmt"))]
+    #[test]
+    // The explicit borrows are needed for autoref specialization.
+    #[allow(clippy::needless_borrow)]
+    fn debug_specialization() {
+        #[allow(unused_imports)]
+        use super::__debug::{Opaque, ViaDebug as _, ViaOpaque as _, Wrap};
+
+        // Captured values that implement `Debug` should be selected as-is.
+        let _: &Option<()> = (&Wrap(&None::<()>)).__debug_value();
+        let _: &&bool = (&Wrap(&&false)).__debug_value();
+
+        // Other captured values should be replaced with a placeholder.
+        struct NotDebug;
+        let _: Opaque = (&Wrap(&NotDebug)).__debug_value();
+    }
+
+    #[test]
+    fn r() {
+        fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E>, inner: T) -> i32 {
+            assert_eq!(or_return!(outer), inner);
+            2
+        }
+
+        // Success cases should fall through.
+        let success = 2;
+        assert_eq!(bail(true, true), success);
+        assert_eq!(bail(Some(-1), -1), success);
+        assert_eq!(bail(Ok::<_, ()>(-1), -1), success);
+
+        // Failure cases should return early with the default value.
+        let failure = 0;
+        assert_eq!(bail(false, true), failure);
+        assert_eq!(bail(None, -1), failure);
+        assert_eq!(bail(Err(()), -1), failure);
+    }
+
+    #[test]
+    fn r_with_value() {
+        fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E>, inner: T) -> i32 {
+            assert_eq!(or_return!(1, outer), inner);
+            2
+        }
+
+        // Success cases should fall through.
+        let success = 2;
+        assert_eq!(bail(true, true), success);
+        assert_eq!(bail(Some(-1), -1), success);
+        assert_eq!(bail(Ok::<_, ()>(-1), -1), success);
+
+        // Failure cases should return early with the provided value.
+        let failure = 1;
+        assert_eq!(bail(false, true), failure);
+        assert_eq!(bail(None, -1), failure);
+        assert_eq!(bail(Err(()), -1), failure);
+    }
+
+    /// A function that bails on `None`, and the value it should evaluate to on failure.
+    type Case = (fn(Option<i32>) -> i32, i32);
+
+    #[test]
+    fn r_variants() {
+        let cases: [Case; 5] = [
+            (|x| or_return!(x, quiet_if = |_| true), 0),
+            (|x| or_return!(x; x), 0),
+            (|x| or_return!(4, x; x, else {}), 4),
+            (|x| or_return_escalate!(x), 0),
+            (|x| or_return_escalate!(1, x), 1),
+        ];
+        for (i, (bail, failure)) in cases.into_iter().enumerate() {
+            // Success cases should fall through.
+            assert_eq!(bail(Some(-1)), -1, "case {i}");
+            // Failure cases should return early with the default or provided value.
+            assert_eq!(bail(None), failure, "case {i}");
+        }
+    }
+
+    #[test]
+    fn r_comparison() {
+        fn bail(hp: i32, name: String) -> i32 {
+            or_return!(hp > 0);
+            // The operands should be compared by reference.
+            or_return!(1, name == "player");
+            assert_eq!(name, "player");
+            // Long expressions shouldn't hit the recursion limit.
+            or_return!(
+                3,
+                hp + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    + hp
+                    > 0
+            );
+            or_return!(
+                3,
+                name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    + name.len()
+                    > 0
+            );
+            2
+        }
+
+        // Operands that don't implement `Debug` should still be comparable.
+        #[derive(PartialEq)]
+        struct NotDebug(i32);
+
+        fn bail_not_debug(x: NotDebug) -> i32 {
+            or_return!(1, x == NotDebug(0));
+            2
+        }
+
+        // Success cases should fall through.
+        assert_eq!(bail(1, "player".into()), 2);
+        assert_eq!(bail_not_debug(NotDebug(0)), 2);
+
+        // Failure cases should return early.
+        assert_eq!(bail(0, "player".into()), 0);
+        assert_eq!(bail(1, "enemy".into()), 1);
+        assert_eq!(bail_not_debug(NotDebug(1)), 1);
+    }
+
+    #[test]
+    fn r_closure() {
+        fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E>, inner: T) -> String {
+            assert_eq!(or_return!(|e| format!("{e:?}"), outer), inner);
+            "success".into()
+        }
+
+        // Success cases should fall through.
+        let success = "success";
+        assert_eq!(bail(true, true), success);
+        assert_eq!(bail(Some(-1), -1), success);
+        assert_eq!(bail(Ok::<_, ()>(-1), -1), success);
 
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or!($expr, break)
-    };
-}
+        // Failure cases should return early with the value computed from the failure.
+        assert_eq!(bail(false, true), "false");
+        assert_eq!(bail(None, -1), "None");
+        assert_eq!(bail(Err(3), -1), "3");
+    }
 
-/// A helper macro to unwrap on success, or quietly discard the failure and do something else.
-#[doc(hidden)]
-#[macro_export]
-macro_rules! __unwrap_or_quiet {
-    ($expr:expr, $else:expr) => {
-        match $crate::IntoResult::into_result($expr) {
-            ::core::result::Result::Ok(x) => x,
-            _ => {
-                $else;
-            }
+    #[test]
+    fn r_err() {
+        fn bail(x: Result<i32, &'static str>) -> Result<i32, String> {
+            let x = or_return_err!(x);
+            Ok(x + 1)
         }
-    };
-}
 
-/// Unwrap on success, or quietly discard the failure and return.
-///
-/// Returns [`Default::default()`] unless an initial argument is provided to return instead.
-#[macro_export]
-macro_rules! or_return_quiet {
-    ($return:expr, $expr:expr $(,)?) => {
-        $crate::__unwrap_or_quiet!($expr, return $return)
-    };
+        fn bail_context(x: Result<i32, &'static str>) -> Result<i32, String> {
+            let x = or_return_err!(|e| format!("context: {e}"), x);
+            Ok(x + 1)
+        }
 
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or_quiet!($expr, return ::core::default::Default::default())
-    };
-}
+        // Success cases should fall through.
+        assert_eq!(bail(Ok(1)), Ok(2));
+        assert_eq!(bail_context(Ok(1)), Ok(2));
 
-/// Unwrap on success, or quietly discard the failure and continue.
-///
-/// Accepts an optional 'label as the first argument.
-#[macro_export]
-macro_rules! or_continue_quiet {
-    ($label:tt, $expr:expr $(,)?) => {
-        $crate::__unwrap_or_quiet!($expr, continue $label)
-    };
+        // Failure cases should return early with the converted failure.
+        assert_eq!(bail(Err("error")), Err("error".into()));
+        assert_eq!(bail_context(Err("error")), Err("context: error".into()));
+    }
 
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or_quiet!($expr, continue)
-    };
-}
+    #[test]
+    fn r_bailed() {
+        fn bail(x: Option<i32>) -> Result<i32, Bailed> {
+            let x = or_return_bailed!(x);
+            Ok(x + 1)
+        }
 
-/// Unwrap on success, or quietly discard the failure and break.
-///
-/// Accepts an optional 'label as the first argument.
-#[macro_export]
-macro_rules! or_break_quiet {
-    ($label:tt, $expr:expr $(,)?) => {
-        $crate::__unwrap_or_quiet!($expr, break $label)
-    };
+        fn bail_quiet(x: Result<i32, core::num::ParseIntError>) -> Result<i32, Bailed> {
+            let x = or_return_bailed_quiet!(x, msg = "invalid x");
+            Ok(x + 1)
+        }
 
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or_quiet!($expr, break)
-    };
-}
+        // Success cases should fall through.
+        assert_eq!(bail(Some(1)).unwrap(), 2);
+        assert_eq!(bail_quiet(Ok(1)).unwrap(), 2);
 
-/// A helper macro to unwrap on success, or log the first failure and do something else.
-#[doc(hidden)]
-#[macro_export]
-macro_rules! __unwrap_or_log_once {
-    ($expr:expr, $else:expr) => {
-        match $crate::IntoResult::into_result($expr) {
-            ::core::result::Result::Ok(x) => x,
-            ::core::result::Result::Err(__err) => {
-                static __SHOULD_LOG: ::core::sync::atomic::AtomicBool =
-                    ::core::sync::atomic::AtomicBool::new(true);
-                if __SHOULD_LOG.swap(false, ::core::sync::atomic::Ordering::Relaxed) {
-                    $crate::__log_bail!($expr, __err);
-                }
-                $else;
-            }
+        // Failure cases should return early with the bail site.
+        let bailed = bail(None).unwrap_err();
+        assert_eq!(bailed.site().expr, "x");
+        let bailed = bail_quiet("x".parse()).unwrap_err();
+        assert_eq!(bailed.site().expr, "x");
+        assert_eq!(bailed.site().msg, Some("invalid x"));
+    }
+
+    #[test]
+    fn r_retry() {
+        fn bail(fails: u32) -> u32 {
+            let mut calls = 0;
+            or_retry!(3, calls + 100, {
+                calls += 1;
+                calls > fails
+            });
+            calls
         }
-    };
-}
 
-/// Unwrap on success, or log the first failure and return.
-///
-/// Returns [`Default::default()`] unless an initial argument is provided to return instead.
-#[macro_export]
-macro_rules! or_return_log_once {
-    ($return:expr, $expr:expr $(,)?) => {
-        $crate::__unwrap_or_log_once!($expr, return $return)
-    };
+        // Success cases should fall through after retrying.
+        assert_eq!(bail(0), 1);
+        assert_eq!(bail(2), 3);
 
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or_log_once!($expr, return ::core::default::Default::default())
-    };
-}
+        // Failure cases should return early after the last attempt.
+        assert_eq!(bail(3), 103);
+    }
 
-/// Unwrap on success, or log the first failure and continue.
-///
-/// Accepts an optional 'label as the first argument.
-#[macro_export]
-macro_rules! or_continue_log_once {
-    ($label:tt, $expr:expr $(,)?) => {
-        $crate::__unwrap_or_log_once!($expr, continue $label)
-    };
+    #[test]
+    fn backoff() {
+        use std::time::Duration;
 
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or_log_once!($expr, continue)
-    };
-}
+        // The backoff should double after each failed attempt, up to a limit.
+        let backoff = Duration::from_millis(5);
+        assert_eq!(crate::__backoff(backoff, 1), backoff);
+        assert_eq!(crate::__backoff(backoff, 2), backoff * 2);
+        assert_eq!(crate::__backoff(backoff, 3), backoff * 4);
+        assert_eq!(crate::__backoff(backoff, 100), backoff * (1 << 31));
+        assert_eq!(crate::__backoff(Duration::ZERO, 3), Duration::ZERO);
+        // Long backoffs should saturate instead of overflowing.
+        assert_eq!(crate::__backoff(Duration::MAX, 2), Duration::MAX);
+    }
 
-/// Unwrap on success, or log the first failure and break.
-///
-/// Accepts an optional 'label as the first argument.
-#[macro_export]
-macro_rules! or_break_log_once {
-    ($label:tt, $expr:expr $(,)?) => {
-        $crate::__unwrap_or_log_once!($expr, break $label)
-    };
+    #[test]
+    fn or_exit() {
+        fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E>, inner: T) -> i32 {
+            assert_eq!(or_exit!(3, outer), inner);
+            2
+        }
 
-    ($expr:expr $(,)?) => {
-        $crate::__unwrap_or_log_once!($expr, break)
-    };
-}
+        // Failure cases should exit, so run them in a child process.
+        if let Ok(location) = std::env::var("TINY_BAIL_EXIT_LOCATION") {
+            crate::set_exit_location(location == "1");
+            bail(None, -1);
+            unreachable!();
+        }
 
-#[cfg(test)]
-mod tests {
-    use std::fmt::Debug;
+        // Success cases should fall through.
+        let success = 2;
+        assert_eq!(bail(true, true), success);
+        assert_eq!(bail(Some(-1), -1), success);
+        assert_eq!(bail(Ok::<_, ()>(-1), -1), success);
 
-    use super::IntoResult;
+        // Failure cases should print the failure to stderr and exit with the given code.
+        for location in ["0", "1"] {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["tests::or_exit", "--exact", "--nocapture"])
+                .env("TINY_BAIL_EXIT_LOCATION", location)
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(3));
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            assert!(stderr.starts_with("error: `outer` is `None`"), "{stderr}");
+            // The code location should only be included if enabled.
+            assert_eq!(
+                stderr.contains(" (at src/lib.rs:"),
+                location == "1",
+                "{stderr}"
+            );
+        }
+    }
 
     #[test]
-    fn r() {
+    fn bail() {
         fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E>, inner: T) -> i32 {
-            assert_eq!(or_return!(outer), inner);
+            assert_eq!(
+                crate::bail!(outer, on_fail = return 4, level = debug),
+                inner
+            );
             2
         }
 
@@ -446,17 +3804,77 @@ mod tests {
         assert_eq!(bail(Some(-1), -1), success);
         assert_eq!(bail(Ok::<_, ()>(-1), -1), success);
 
-        // Failure cases should return early with the default value.
-        let failure = 0;
+        // Failure cases should return early with the value.
+        let failure = 4;
         assert_eq!(bail(false, true), failure);
         assert_eq!(bail(None, -1), failure);
         assert_eq!(bail(Err(()), -1), failure);
     }
 
     #[test]
-    fn r_with_value() {
+    fn bail_with_options() {
+        let mut sum = 0;
+        '_a: for x in [Some(1), None, Some(4)] {
+            for y in [Some(2), None] {
+                sum += crate::bail!(
+                    x,
+                    on_fail = continue '_a,
+                    log = once,
+                    level = warn,
+                    msg = "missing x";
+                    y,
+                );
+                sum += crate::bail!(y, on_fail = continue, log = escalate, msg = "missing y");
+            }
+        }
+
+        // Failure cases should continue the given loop.
+        assert_eq!(sum, 1 + 2 + 1 + 4 + 2 + 4);
+
+        let value = '_b: {
+            let x = crate::bail!(None::<i32>, on_fail = break '_b => 3, log = quiet);
+            x + 1
+        };
+        // Failure cases should break out of the labeled block with the value.
+        assert_eq!(value, 3);
+
+        // Failure cases should fall back to the value.
+        assert_eq!(crate::bail!(None, on_fail = default 5), 5);
+        assert_eq!(crate::bail!(Err::<i32, ()>(()), on_fail = default), 0);
+    }
+
+    #[test]
+    fn aliases() {
+        // Aliases can be taken from the full macro names or the tiny aliases.
+        crate::aliases!(test_ret = or_return, test_cont = c, test_brk = be);
+
+        fn bail(values: &[Option<i32>]) -> i32 {
+            test_ret!(!values.is_empty());
+            let mut sum = 0;
+            for &value in values {
+                sum += test_cont!(value);
+            }
+            for &value in values {
+                sum += test_brk!(value);
+            }
+            sum
+        }
+
+        // The aliases should bail like the macros they alias.
+        assert_eq!(bail(&[]), 0);
+        assert_eq!(bail(&[Some(1), None, Some(4)]), 1 + 4 + 1);
+    }
+
+    #[test]
+    fn define_bail_macros() {
+        crate::define_bail_macros! {
+            level: error,
+            msg: "default",
+            aliases: (test_r, test_c, test_b),
+        }
+
         fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E>, inner: T) -> i32 {
-            assert_eq!(or_return!(1, outer), inner);
+            assert_eq!(test_r!(4, outer, level = debug), inner);
             2
         }
 
@@ -466,11 +3884,135 @@ mod tests {
         assert_eq!(bail(Some(-1), -1), success);
         assert_eq!(bail(Ok::<_, ()>(-1), -1), success);
 
-        // Failure cases should return early with the provided value.
-        let failure = 1;
+        // Failure cases should return early with the value.
+        let failure = 4;
         assert_eq!(bail(false, true), failure);
         assert_eq!(bail(None, -1), failure);
         assert_eq!(bail(Err(()), -1), failure);
+
+        let mut sum = 0;
+        for x in [Some(1), None, Some(4)] {
+            sum += test_c!(x, log = quiet);
+        }
+        for x in [Some(1), None, Some(4)] {
+            sum += test_b!(x, msg = "override");
+        }
+        // Failure cases should continue and break.
+        assert_eq!(sum, 1 + 4 + 1);
+
+        mod strict {
+            crate::define_bail_macros! {
+                msg: "strict",
+                strict: true,
+                aliases: (strict_r, strict_c, strict_b),
+            }
+
+            pub fn bail(x: Option<i32>) -> i32 {
+                strict_r!(4, x) + strict_r!(x, level = debug; x)
+            }
+
+            pub fn bail_loop(x: Option<i32>) -> i32 {
+                let mut sum = 0;
+                '_a: for _ in 0..2 {
+                    sum += strict_c!(x) + strict_b!('_a, x);
+                }
+                sum
+            }
+        }
+
+        // Success cases should fall through.
+        assert_eq!(strict::bail(Some(1)), 2);
+        assert_eq!(strict::bail_loop(Some(1)), 4);
+
+        // Failure cases should panic with the bail message instead of bailing.
+        for f in [strict::bail, strict::bail_loop] {
+            let err = std::panic::catch_unwind(|| f(None)).unwrap_err();
+            let message = err.downcast_ref::<String>().unwrap();
+            assert!(
+                message.starts_with("strict: Bailed at src/lib.rs:"),
+                "{message}"
+            );
+            assert!(message.ends_with(": `x` is `None`"), "{message}");
+        }
+    }
+
+    #[test]
+    fn r_turbofish() {
+        fn bail(key: i32) -> usize {
+            // Commas inside turbofish generics shouldn't split arguments.
+            or_return!(HashMap::<i32, Vec<u8>>::from([(1, vec![2])]).get(&key)).len()
+                + or_return!(Vec::<HashMap<u8, u8>>::new().len() == 0; key)
+                    .then_some(1)
+                    .unwrap()
+        }
+
+        assert_eq!(bail(1), 2);
+        assert_eq!(bail(2), 0);
+    }
+
+    #[test]
+    fn r_pattern() {
+        #[derive(Debug)]
+        enum Shape {
+            Circle { r: i32 },
+            Square(i32),
+        }
+
+        fn bail_let(shape: Shape) -> i32 {
+            or_return!(let Shape::Circle { r } = shape);
+            r
+        }
+
+        fn bail_value(shape: &Shape) -> i32 {
+            or_return!(1, Shape::Square(x) = shape => *x)
+        }
+
+        // Success cases should fall through.
+        assert_eq!(bail_let(Shape::Circle { r: 2 }), 2);
+        assert_eq!(bail_value(&Shape::Square(2)), 2);
+
+        // Failure cases should return early.
+        assert_eq!(bail_let(Shape::Square(2)), 0);
+        assert_eq!(bail_value(&Shape::Circle { r: 2 }), 1);
+    }
+
+    #[test]
+    fn r_all() {
+        fn bail(a: Option<i32>, b: Result<i32, ()>, c: bool) -> i32 {
+            let (a, b, _) = or_return_all!(a, b, c);
+            a + b
+        }
+
+        // Success cases should fall through.
+        assert_eq!(bail(Some(1), Ok(2), true), 3);
+
+        // Failure cases should return early with the default value.
+        assert_eq!(bail(None, Ok(2), true), 0);
+        assert_eq!(bail(Some(1), Err(()), true), 0);
+        assert_eq!(bail(Some(1), Ok(2), false), 0);
+    }
+
+    #[test]
+    fn r_chain() {
+        fn bail(x: &[Option<i32>]) -> i32 {
+            or_return_chain!(x.first()?.as_ref()?.checked_add(1)?)
+        }
+
+        fn bail_infallible(x: &[Vec<i32>]) -> usize {
+            // The last step shouldn't be unwrapped.
+            or_return_chain!(x.first()?.first()?.count_ones() as usize)
+        }
+
+        // Success cases should fall through.
+        assert_eq!(bail(&[Some(1)]), 2);
+        assert_eq!(bail_infallible(&[vec![3]]), 2);
+
+        // Failure cases should return early at any step.
+        assert_eq!(bail(&[]), 0);
+        assert_eq!(bail(&[None]), 0);
+        assert_eq!(bail(&[Some(i32::MAX)]), 0);
+        assert_eq!(bail_infallible(&[]), 0);
+        assert_eq!(bail_infallible(&[vec![]]), 0);
     }
 
     #[test]
@@ -611,6 +4153,105 @@ mod tests {
         assert_eq!(bail(Err(()), -1), failure);
     }
 
+    /// Count the steps taken through two nested loops, with `$bail` unwrapping `Some(-1)` in the
+    /// inner loop. The outer loop is labeled `$label`.
+    macro_rules! nested_loops {
+        ($label:lifetime, $bail:expr) => {{
+            let mut val = 0;
+            $label: for _ in 0..2 {
+                val += 1;
+                for _ in 0..2 {
+                    val += 1;
+                    assert_eq!($bail, -1);
+                    val += 1;
+                }
+                val += 1;
+            }
+            val
+        }};
+    }
+
+    #[test]
+    fn c_variants() {
+        let cases: [Case; 5] = [
+            (
+                |x| nested_loops!('_a, or_continue!('_a, x, quiet_if = |_| true)),
+                4,
+            ),
+            (|x| nested_loops!('_a, or_continue!('_a, x; x)), 4),
+            (
+                |x| nested_loops!('_a, or_continue_quiet!('_a, x; else {})),
+                4,
+            ),
+            (|x| nested_loops!('_a, or_continue_escalate!(x)), 8),
+            (|x| nested_loops!('_a, or_continue_escalate!('_a, x)), 4),
+        ];
+        for (i, (bail, failure)) in cases.into_iter().enumerate() {
+            // Success cases should fall through.
+            assert_eq!(bail(Some(-1)), 12, "case {i}");
+            // Failure cases should continue early to the inner or outer loop.
+            assert_eq!(bail(None), failure, "case {i}");
+        }
+    }
+
+    #[test]
+    fn c_pattern() {
+        let mut sum = 0;
+        for x in [Ok(1), Err(2), Ok(4)] {
+            or_continue!(let Ok::<i32, i32>(x) = x);
+            sum += x;
+        }
+
+        // Failure cases should continue past the rest of the loop body.
+        assert_eq!(sum, 5);
+    }
+
+    #[test]
+    fn c_all_with_label() {
+        let mut sum = 0;
+        '_a: for x in [Some(1), None, Some(4)] {
+            for y in [Ok::<_, ()>(2), Err(())] {
+                let (x, y) = or_continue_all!('_a, x, y);
+                sum += x + y;
+            }
+        }
+
+        // Failure cases should continue early to the outer loop.
+        assert_eq!(sum, 9);
+    }
+
+    #[test]
+    fn c_chain_with_label() {
+        let mut sum = 0;
+        '_a: for x in [[Some(1), None], [None, Some(2)], [Some(4), Some(8)]] {
+            for i in 0..2 {
+                sum += or_continue_chain!('_a, x.get(i)?.as_ref()?);
+            }
+        }
+
+        // Failure cases should continue early to the outer loop.
+        assert_eq!(sum, 13);
+    }
+
+    #[test]
+    fn c_attempts() {
+        let mut calls = 0;
+        for fails in 0..3 {
+            let mut attempt = 0;
+            or_continue!(
+                {
+                    calls += 1;
+                    attempt += 1;
+                    attempt > fails
+                },
+                attempts = 2,
+            );
+        }
+
+        // Failure cases should continue after the last attempt.
+        assert_eq!(calls, 5);
+    }
+
     #[test]
     fn cq() {
         fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E> + Copy, inner: T) -> i32 {
@@ -726,6 +4367,7 @@ mod tests {
         assert_eq!(bail(None, -1), failure);
         assert_eq!(bail(Err(()), -1), failure);
     }
+
     #[test]
     fn b() {
         fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E> + Copy, inner: T) -> i32 {
@@ -784,6 +4426,68 @@ mod tests {
         assert_eq!(bail(Err(()), -1), failure);
     }
 
+    #[test]
+    fn b_variants() {
+        let cases: [Case; 5] = [
+            (|x| nested_loops!('_a, or_break!(x, quiet_if = |_| true)), 6),
+            (|x| nested_loops!('_a, or_break!(x; x)), 6),
+            (|x| nested_loops!('_a, or_break!(x; x, else {})), 6),
+            (|x| nested_loops!('_a, or_break_escalate!(x)), 6),
+            (|x| nested_loops!('_a, or_break_escalate!('_a, x)), 2),
+        ];
+        for (i, (bail, failure)) in cases.into_iter().enumerate() {
+            // Success cases should fall through.
+            assert_eq!(bail(Some(-1)), 12, "case {i}");
+            // Failure cases should break early from the inner or outer loop.
+            assert_eq!(bail(None), failure, "case {i}");
+        }
+    }
+
+    #[test]
+    fn b_with_value() {
+        fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E>, inner: T) -> i32 {
+            '_a: {
+                assert_eq!(or_break!('_a => 1, outer), inner);
+                2
+            }
+        }
+
+        // Success cases should fall through.
+        let success = 2;
+        assert_eq!(bail(true, true), success);
+        assert_eq!(bail(Some(-1), -1), success);
+        assert_eq!(bail(Ok::<_, ()>(-1), -1), success);
+
+        // Failure cases should break out of the labeled block with the value.
+        let failure = 1;
+        assert_eq!(bail(false, true), failure);
+        assert_eq!(bail(None, -1), failure);
+        assert_eq!(bail(Err(()), -1), failure);
+    }
+
+    #[test]
+    fn b_all() {
+        let mut sum = 0;
+        for (x, y) in [(Some(1), Some(2)), (Some(4), None), (Some(8), Some(16))] {
+            let (x, y) = or_break_all!(x, y);
+            sum += x + y;
+        }
+
+        // Failure cases should break out of the loop.
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn b_chain() {
+        let mut sum = 0;
+        for x in [vec![Some(1)], vec![None], vec![Some(4)]] {
+            sum += or_break_chain!(x.first()?.as_ref()?);
+        }
+
+        // Failure cases should break out of the loop.
+        assert_eq!(sum, 1);
+    }
+
     #[test]
     fn bq() {
         fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E> + Copy, inner: T) -> i32 {
@@ -842,6 +4546,47 @@ mod tests {
         assert_eq!(bail(Err(()), -1), failure);
     }
 
+    #[test]
+    fn bq_with_value() {
+        let mut i = 0;
+        let val = '_a: loop {
+            i += 1;
+            or_break_quiet!('_a => i, i < 3);
+        };
+
+        // Failure cases should break out of the loop with the value.
+        assert_eq!(val, 3);
+    }
+
+    #[test]
+    fn or_default() {
+        fn bail<T: Eq + Debug + Default, E: Debug>(outer: impl IntoResult<T, E>, inner: T) -> T {
+            let value = or_default!(outer);
+            assert_eq!(value, inner);
+            value
+        }
+
+        // Success cases should evaluate to the value.
+        assert!(bail(true, true));
+        assert_eq!(bail(Some(-1), -1), -1);
+        assert_eq!(bail(Ok::<_, ()>(-1), -1), -1);
+
+        // Failure cases should fall back to the default value.
+        assert!(!bail(false, false));
+        assert_eq!(bail(None, 0), 0);
+        assert_eq!(bail(Err(()), 0), 0);
+    }
+
+    #[test]
+    fn or_fallback() {
+        let x: Result<i32, i32> = Err(3);
+
+        // Failure cases should fall back to the given value, or compute it from the failure.
+        assert_eq!(or_default!(1, x), 1);
+        assert_eq!(or_default_quiet!(|e| e * 2, x), 6);
+        assert_eq!(or_default_log_once!(Some(x) = x.ok() => x + 1), 0);
+    }
+
     #[test]
     fn bo() {
         fn bail<T: Eq + Debug, E: Debug>(outer: impl IntoResult<T, E> + Copy, inner: T) -> i32 {
@@ -0,0 +1,219 @@
+use crate::{Level, level};
+
+/// A trait for failure values that choose their own log level on bail.
+///
+/// This trait is consulted by every bail macro that logs, unless a `level` option is given. It's
+/// implemented for common `std` errors, so that expected errors like `TryRecvError::Empty` bail
+/// quietly while unexpected errors like `TryRecvError::Disconnected` are logged at the `error`
+/// level.
+///
+/// A failure that bails quietly doesn't use up the single log of the `log_once` macros, or count
+/// toward escalation in the `escalate` macros.
+///
+/// This trait is resolved at the macro call site, so a generic failure value will always be logged
+/// at the current log level.
+///
+/// # Example
+///
+/// ```rust
+/// use tiny_bail::{BailPolicy, Level, prelude::*};
+///
+/// #[derive(Debug)]
+/// enum FetchError {
+///     NotReady,
+///     Corrupted,
+/// }
+///
+/// impl BailPolicy for FetchError {
+///     fn bail_level(&self) -> Level {
+///         match self {
+///             // Bail quietly.
+///             Self::NotReady => Level::Off,
+///             // Bail at the current log level.
+///             Self::Corrupted => tiny_bail::level(),
+///         }
+///     }
+/// }
+///
+/// fn fetch() -> Result<i32, FetchError> {
+///     Err(FetchError::NotReady)
+/// }
+///
+/// fn process() {
+///     let value = r!(fetch());
+/// }
+/// ```
+pub trait BailPolicy {
+    /// Return the log level for this failure value, or [`Level::Off`] to bail quietly.
+    ///
+    /// Defaults to the current log level.
+    fn bail_level(&self) -> Level {
+        level()
+    }
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::{
+        io,
+        sync::{
+            TryLockError,
+            mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError},
+        },
+    };
+
+    use super::BailPolicy;
+    use crate::{Level, level};
+
+    impl BailPolicy for io::ErrorKind {
+        fn bail_level(&self) -> Level {
+            match self {
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => Level::Off,
+                _ => level(),
+            }
+        }
+    }
+
+    impl BailPolicy for io::Error {
+        fn bail_level(&self) -> Level {
+            self.kind().bail_level()
+        }
+    }
+
+    impl BailPolicy for TryRecvError {
+        fn bail_level(&self) -> Level {
+            match self {
+                TryRecvError::Empty => Level::Off,
+                TryRecvError::Disconnected => Level::Error,
+            }
+        }
+    }
+
+    impl BailPolicy for RecvTimeoutError {
+        fn bail_level(&self) -> Level {
+            match self {
+                RecvTimeoutError::Timeout => Level::Off,
+                RecvTimeoutError::Disconnected => Level::Error,
+            }
+        }
+    }
+
+    impl BailPolicy for RecvError {
+        fn bail_level(&self) -> Level {
+            Level::Error
+        }
+    }
+
+    impl<T> BailPolicy for TrySendError<T> {
+        fn bail_level(&self) -> Level {
+            match self {
+                TrySendError::Full(_) => Level::Off,
+                TrySendError::Disconnected(_) => Level::Error,
+            }
+        }
+    }
+
+    impl<T> BailPolicy for SendError<T> {
+        fn bail_level(&self) -> Level {
+            Level::Error
+        }
+    }
+
+    impl<T> BailPolicy for TryLockError<T> {
+        fn bail_level(&self) -> Level {
+            match self {
+                TryLockError::WouldBlock => Level::Off,
+                TryLockError::Poisoned(_) => Level::Error,
+            }
+        }
+    }
+}
+
+/// A wrapper around the failure value.
+pub struct Wrap<'a, T>(pub &'a T);
+
+/// Select the failure value's [`BailPolicy`] if it implements it.
+pub trait ViaPolicy {
+    /// Return the log level for the failure value.
+    fn __bail_level(&self) -> Level;
+}
+
+impl<T: BailPolicy> ViaPolicy for Wrap<'_, T> {
+    fn __bail_level(&self) -> Level {
+        // Respect the current log level if bail messages have been silenced.
+        match level() {
+            Level::Off => Level::Off,
+            _ => self.0.bail_level(),
+        }
+    }
+}
+
+/// Select the current log level otherwise.
+pub trait ViaDefault {
+    /// Return the log level for the failure value.
+    fn __bail_level(&self) -> Level;
+}
+
+impl<T> ViaDefault for &Wrap<'_, T> {
+    fn __bail_level(&self) -> Level {
+        level()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BailPolicy, Wrap};
+    use crate::Level;
+
+    #[test]
+    // The explicit borrows are needed for autoref specialization.
+    #[allow(clippy::needless_borrow)]
+    fn specialization() {
+        #[allow(unused_imports)]
+        use super::{ViaDefault as _, ViaPolicy as _};
+
+        struct Quiet;
+        impl BailPolicy for Quiet {
+            fn bail_level(&self) -> Level {
+                Level::Off
+            }
+        }
+
+        struct Loud;
+        impl BailPolicy for Loud {
+            fn bail_level(&self) -> Level {
+                Level::Error
+            }
+        }
+
+        let _lock = crate::tests::LOCK.lock().unwrap();
+        let level = crate::level();
+        crate::set_level(Level::Info);
+
+        // Failure values that implement `BailPolicy` should choose their own log level.
+        assert_eq!((&Wrap(&Quiet)).__bail_level(), Level::Off);
+        assert_eq!((&Wrap(&Loud)).__bail_level(), Level::Error);
+
+        // Other failure values should use the current log level.
+        assert_eq!((&Wrap(&None::<()>)).__bail_level(), Level::Info);
+
+        // Silencing bail messages should take precedence.
+        crate::set_level(Level::Off);
+        assert_eq!((&Wrap(&Loud)).__bail_level(), Level::Off);
+
+        crate::set_level(level);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn std_impls() {
+        use std::{io, sync::mpsc::TryRecvError};
+
+        // Expected errors should bail quietly.
+        assert_eq!(TryRecvError::Empty.bail_level(), Level::Off);
+        assert_eq!(io::ErrorKind::WouldBlock.bail_level(), Level::Off);
+
+        // Unexpected errors should be logged at the `error` level.
+        assert_eq!(TryRecvError::Disconnected.bail_level(), Level::Error);
+    }
+}
@@ -0,0 +1,47 @@
+//! Checks that `tiny_bail` builds for `no_std` targets.
+//!
+//! ```shell
+//! cargo build --manifest-path tests/no_std/Cargo.toml --target thumbv7em-none-eabihf
+//! ```
+
+#![no_std]
+
+use tiny_bail::prelude::{brk::*, cont::*, ret::*};
+
+/// Register a sink for bail messages.
+#[cfg(not(feature = "defmt"))]
+pub fn init() {
+    fn sink(
+        _site: &tiny_bail::BailSite,
+        _level: tiny_bail::Level,
+        _err: &dyn core::fmt::Debug,
+        _captures: &[tiny_bail::fallback::Capture],
+    ) {
+    }
+    tiny_bail::fallback::set_sink(sink);
+}
+
+/// Exercise the return macros.
+pub fn bail_return(x: Option<i32>) -> i32 {
+    let a = r!(x; x);
+    let b = rq!(x);
+    let c = ro!(x);
+    let d = re!(x);
+    a + b + c + d
+}
+
+/// Exercise the continue and break macros.
+pub fn bail_loop(xs: &[Result<i32, ()>]) -> i32 {
+    let mut sum = 0;
+    for &x in xs {
+        sum += c!(x);
+        sum += cq!(x);
+        sum += co!(x);
+        sum += ce!(x);
+        sum += b!(x);
+        sum += bq!(x);
+        sum += bo!(x);
+        sum += be!(x);
+    }
+    sum
+}